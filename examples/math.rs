@@ -8,7 +8,9 @@ const PHI: f64 = 1.618033988749894848204586834365638118_f64;
 fn main() -> ExitCode {
     let file = fs::read_to_string("examples/math.txt").expect("unable to open `math.txt`");
 
-    let mut reporter = TerminalReporter::default();
+    let mut reporter = TerminalReporter::default()
+        .with_summary(true)
+        .with_max_errors(Some(50));
     let lookup = reporter.register_file("math.txt", file.clone());
     let eof = reporter.eof_span(lookup);
 
@@ -20,16 +22,21 @@ fn main() -> ExitCode {
 
         match token {
             Ok(tok) => tokens.push(Spanned::new(tok, span)),
-            Err(err) => reporter.report(if err.is_empty() {
-                error!(span, "unknown token")
-            } else {
-                error!(span, "{err}")
-            }),
+            Err(err) => {
+                let signal = reporter.report(if err.is_empty() {
+                    error!(span, "unknown token")
+                } else {
+                    error!(span, "{err}")
+                });
+
+                if signal.is_abort() {
+                    break;
+                }
+            }
         }
     }
 
     if reporter.has_errors() {
-        reporter.report(error!("unable to compile due to previous syntax errors"));
         let _ = reporter.emit_all();
 
         return ExitCode::FAILURE;
@@ -37,7 +44,6 @@ fn main() -> ExitCode {
 
     let expr = parse_expression(&tokens, &mut 0, &mut reporter, eof);
     if reporter.has_errors() {
-        reporter.report(error!("unable to compile due to previous syntax errors"));
         let _ = reporter.emit_all();
 
         return ExitCode::FAILURE;
@@ -243,7 +249,7 @@ fn parse_expression(
 ) -> Spanned<Expr> {
     let mut a = parse_terminal(stream, index, reporter, eof);
 
-    while let Some(tok) = stream.get(*index).as_deref() {
+    while let Some(tok) = stream.get(*index) {
         match tok.inner() {
             Token::Plus => {
                 *index += 1;
@@ -263,7 +269,7 @@ fn parse_expression(
         }
     }
 
-    return a;
+    a
 }
 
 fn parse_terminal(
@@ -274,7 +280,7 @@ fn parse_terminal(
 ) -> Spanned<Expr> {
     let mut a = parse_factor(stream, index, reporter, eof);
 
-    while let Some(tok) = stream.get(*index).as_deref() {
+    while let Some(tok) = stream.get(*index) {
         match tok.inner() {
             Token::Star => {
                 *index += 1;
@@ -327,7 +333,7 @@ fn parse_terminal(
         }
     }
 
-    return a;
+    a
 }
 
 fn parse_factor(
@@ -411,7 +417,7 @@ fn parse_factor(
         }
         None => {
             reporter.report(error!(eof, "expected expression, found `eof`"));
-            return Spanned::new(Expr::Err, eof);
+            Spanned::new(Expr::Err, eof)
         }
     }
 }
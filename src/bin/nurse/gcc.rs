@@ -0,0 +1,135 @@
+//! Diagnostics emitted by GCC and Clang with `-fdiagnostics-format=json`.
+
+use nurse::{prelude::*, Level};
+use serde::Deserialize;
+
+use crate::{
+    diagnostic,
+    sources::{Sources, Unit},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GccDiagnostic {
+    kind: String,
+    message: String,
+    option: Option<String>,
+    #[serde(default)]
+    locations: Vec<Location>,
+    #[serde(default)]
+    children: Vec<GccDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Location {
+    caret: Position,
+    start: Option<Position>,
+    finish: Option<Position>,
+    label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Position {
+    file: String,
+    line: usize,
+    column: usize,
+    #[serde(rename = "byte-column")]
+    byte_column: Option<usize>,
+}
+
+fn level(kind: &str) -> Level {
+    match kind {
+        "warning" | "pedwarn" => Level::Warn,
+        "note" => Level::Info,
+        _ => Level::Error,
+    }
+}
+
+impl Position {
+    fn location(&self, sources: &mut Sources) -> Option<nurse::Location> {
+        let column = self.byte_column.unwrap_or(self.column);
+        sources.location(&self.file, self.line, column, Unit::Bytes)
+    }
+}
+
+impl Location {
+    /// Converts the location into a span,
+    /// extending from its start, or caret if it has none, to the end of its finish.
+    fn span(&self, sources: &mut Sources) -> Option<Span> {
+        let start = self.start.as_ref().unwrap_or(&self.caret);
+        let finish = self.finish.as_ref().unwrap_or(&self.caret);
+
+        let begin = start.location(sources)?;
+        // The finish is inclusive, so the span ends after the character it points to
+        let end = finish.location(sources)?;
+        let end = sources.next_char(&finish.file, end);
+
+        sources.span_between(&start.file, begin, end)
+    }
+}
+
+impl GccDiagnostic {
+    pub fn convert(self, sources: &mut Sources) -> Diagnostic {
+        let mut converted = diagnostic(level(&self.kind), self.message).with_code(self.option);
+
+        for (i, location) in self.locations.iter().enumerate() {
+            let Some(span) = location.span(sources) else {
+                continue;
+            };
+
+            if i == 0 {
+                converted.set_span(Some(span));
+            }
+            if let Some(label) = &location.label {
+                converted.add_spanned_note(label.as_str(), span);
+            }
+        }
+
+        for child in self.children {
+            let span = child
+                .locations
+                .first()
+                .and_then(|location| location.span(sources));
+
+            match span {
+                Some(span) => converted.add_spanned_note(child.message, span),
+                None => converted.add_note(child.message),
+            };
+        }
+
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::{fixture, ranges},
+        Format,
+    };
+
+    #[test]
+    fn convert_fixture() {
+        let (sources, diagnostics) = fixture("gcc.json", Format::Gcc);
+
+        assert_eq!(
+            ranges(&diagnostics),
+            [
+                (
+                    "initialization of 'int' from 'char *' makes integer from pointer without a cast",
+                    Some(29..37)
+                ),
+                ("'x' undeclared (first use in this function)", Some(50..51)),
+                ("line out of range", None),
+                ("column out of range", None),
+                ("in a missing file", None),
+            ]
+        );
+        assert_eq!(diagnostics[0].code(), Some("-Wint-conversion"));
+
+        let note = &diagnostics[1].notes()[0];
+        assert_eq!(note.span().map(|span| span.range()), Some(50..51));
+
+        // The missing file is reported as a warning
+        assert!(!sources.reporter().is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+//! Renders diagnostics produced by other tools with nurse's terminal formatting.
+//!
+//! Reads JSON diagnostics from stdin in one of the following formats,
+//! detecting the format of each value unless one is given with `--format`:
+//!
+//! - `nurse`: diagnostics serialized with nurse's `serde` feature
+//! - `rustc`: `rustc --error-format=json`, including messages wrapped by `cargo --message-format=json`
+//! - `gcc`: GCC or Clang with `-fdiagnostics-format=json`
+//! - `sarif`: SARIF 2.1 logs from static analysis tools
+//!
+//! Source files are read from the paths given in the diagnostics.
+
+#[cfg(feature = "smol")]
+compile_error!("the `nurse` binary does not support the `smol` feature");
+
+mod gcc;
+mod rustc;
+mod sarif;
+mod sources;
+
+use std::{
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use nurse::{prelude::*, Level};
+use serde_json::{Deserializer, Value};
+
+use sources::Sources;
+
+const USAGE: &str = "\
+Renders diagnostics produced by other tools.
+
+Usage: nurse [--format <FORMAT>] < diagnostics.json
+
+Options:
+  -f, --format <FORMAT>  The format of the input [default: auto]
+                         [possible values: auto, nurse, rustc, gcc, sarif]
+  -h, --help             Print help
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Nurse,
+    Rustc,
+    Gcc,
+    Sarif,
+}
+
+impl Format {
+    fn parse(name: &str) -> Option<Option<Format>> {
+        match name {
+            "auto" => Some(None),
+            "nurse" => Some(Some(Format::Nurse)),
+            "rustc" => Some(Some(Format::Rustc)),
+            "gcc" | "clang" => Some(Some(Format::Gcc)),
+            "sarif" => Some(Some(Format::Sarif)),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Nurse => "nurse",
+            Format::Rustc => "rustc",
+            Format::Gcc => "gcc",
+            Format::Sarif => "sarif",
+        }
+    }
+
+    /// Detects the format of a single value from the fields it contains.
+    fn detect(value: &Value) -> Option<Format> {
+        let has = |field| value.get(field).is_some();
+
+        if has("runs") {
+            Some(Format::Sarif)
+        } else if has("spans") && has("children") {
+            Some(Format::Rustc)
+        } else if has("kind") && has("locations") {
+            Some(Format::Gcc)
+        } else if has("level") && has("message") {
+            Some(Format::Nurse)
+        } else {
+            None
+        }
+    }
+}
+
+/// Creates a diagnostic with the given level.
+fn diagnostic(level: Level, message: String) -> Diagnostic {
+    match level {
+        Level::Error => Diagnostic::error(message),
+        Level::Warn => Diagnostic::warning(message),
+        Level::Info => Diagnostic::info(message),
+        Level::Debug => Diagnostic::debug(message),
+    }
+}
+
+/// Reads every JSON value from `input`,
+/// accepting a single document as well as a stream of whitespace-separated values.
+/// Arrays at the top level are flattened.
+fn values(input: &str) -> Result<Vec<Value>, serde_json::Error> {
+    let mut values = Vec::new();
+
+    for value in Deserializer::from_str(input).into_iter::<Value>() {
+        match value? {
+            Value::Array(elements) => values.extend(elements),
+            value => values.push(value),
+        }
+    }
+
+    Ok(values)
+}
+
+/// Removes spans from a nurse diagnostic that refer to unreadable files or lie outside of them,
+/// registering the files of the remaining spans.
+fn resolve_spans(value: &mut Value, sources: &mut Sources) {
+    let mut resolve = |span: Option<&mut Value>| {
+        let Some(span) = span.filter(|span| !span.is_null()) else {
+            return;
+        };
+
+        let file = span.get("file").and_then(Value::as_str);
+        let start = span.get("start").and_then(Value::as_u64);
+        let end = span.get("end").and_then(Value::as_u64);
+
+        let valid = match (file, start, end) {
+            (Some(file), Some(start), Some(end)) => {
+                sources.span(file, start as usize..end as usize).is_some()
+            }
+            _ => false,
+        };
+
+        if !valid {
+            *span = Value::Null;
+        }
+    };
+
+    resolve(value.get_mut("span"));
+    if let Some(Value::Array(notes)) = value.get_mut("notes") {
+        for note in notes {
+            resolve(note.get_mut("span"));
+        }
+    }
+}
+
+fn convert(
+    value: Value,
+    format: Format,
+    sources: &mut Sources,
+) -> Result<Vec<Diagnostic>, serde_json::Error> {
+    Ok(match format {
+        Format::Nurse => {
+            let mut value = value;
+            resolve_spans(&mut value, sources);
+            vec![sources
+                .reporter()
+                .serde_scope(|| serde_json::from_value(value))?]
+        }
+        Format::Rustc => {
+            // Cargo wraps compiler messages alongside unrelated build messages
+            let message = match value.get("reason") {
+                Some(reason) if reason == "compiler-message" => value["message"].clone(),
+                Some(_) => return Ok(Vec::new()),
+                None => value,
+            };
+
+            let message = serde_json::from_value::<rustc::Message>(message)?;
+            if message.is_summary() {
+                return Ok(Vec::new());
+            }
+
+            vec![message.convert(sources)]
+        }
+        Format::Gcc => vec![serde_json::from_value::<gcc::GccDiagnostic>(value)?.convert(sources)],
+        Format::Sarif => serde_json::from_value::<sarif::Log>(value)?.convert(sources),
+    })
+}
+
+fn run(format: Option<Format>) -> Result<bool, String> {
+    let mut input = String::new();
+    io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|err| format!("unable to read stdin: {err}"))?;
+
+    let values = values(&input).map_err(|err| format!("invalid JSON input: {err}"))?;
+    let mut sources = Sources::new(TerminalReporter::default().with_summary(true));
+
+    for value in values {
+        // Cargo's messages are detected by their wrapper rather than their contents
+        let detected = match value.get("reason") {
+            Some(_) => Some(Format::Rustc),
+            None => Format::detect(&value),
+        };
+        let Some(format) = format.or(detected) else {
+            return Err(
+                "unrecognized diagnostic format, try specifying one with `--format`".into(),
+            );
+        };
+
+        let diagnostics = convert(value, format, &mut sources)
+            .map_err(|err| format!("invalid {} diagnostic: {err}", format.name()))?;
+        for diagnostic in diagnostics {
+            sources.reporter_mut().report(diagnostic);
+        }
+    }
+
+    let mut reporter = sources.into_reporter();
+    let failed = reporter.has_errors();
+    reporter
+        .emit_all()
+        .map_err(|err| format!("unable to print diagnostics: {err}"))?;
+
+    Ok(failed)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut format = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            "-f" | "--format" => match args.next().as_deref().and_then(Format::parse) {
+                Some(parsed) => format = parsed,
+                None => {
+                    eprint!("error: expected a format after `{arg}`\n\n{USAGE}");
+                    return ExitCode::from(2);
+                }
+            },
+            _ => {
+                eprint!("error: unexpected argument `{arg}`\n\n{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+
+    match run(format) {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Converts every value in the fixture `name` from the given format.
+    pub fn fixture(name: &str, format: Format) -> (Sources, Vec<Diagnostic>) {
+        let path = format!("tests/fixtures/cli/{name}");
+        let input = std::fs::read_to_string(path).unwrap();
+
+        let mut sources = Sources::new(TerminalReporter::default());
+        let mut diagnostics = Vec::new();
+        for value in values(&input).unwrap() {
+            diagnostics.extend(convert(value, format, &mut sources).unwrap());
+        }
+
+        (sources, diagnostics)
+    }
+
+    /// Gets the message and the range of the span of each diagnostic.
+    pub fn ranges(diagnostics: &[Diagnostic]) -> Vec<(&str, Option<std::ops::Range<usize>>)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.message(), diagnostic.span().map(|s| s.range())))
+            .collect()
+    }
+
+    #[test]
+    fn convert_nurse_fixture() {
+        let (sources, diagnostics) = fixture("nurse.json", Format::Nurse);
+
+        // Spans in unreadable files or outside of their file are removed
+        assert_eq!(
+            ranges(&diagnostics),
+            [
+                ("literal out of range for `u8`", Some(28..31)),
+                ("span out of range", None),
+            ]
+        );
+        assert_eq!(diagnostics[0].code(), Some("E0080"));
+        assert_eq!(diagnostics[0].notes()[0].span(), None);
+        assert!(!sources.reporter().is_empty());
+    }
+
+    #[test]
+    fn detect_fixtures() {
+        let cases = [
+            ("nurse.json", Format::Nurse),
+            ("gcc.json", Format::Gcc),
+            ("sarif.json", Format::Sarif),
+        ];
+
+        for (name, format) in cases {
+            let input = std::fs::read_to_string(format!("tests/fixtures/cli/{name}")).unwrap();
+            for value in values(&input).unwrap() {
+                assert_eq!(Format::detect(&value), Some(format), "{name}");
+            }
+        }
+
+        let message = serde_json::json!({ "message": "", "spans": [], "children": [] });
+        assert_eq!(Format::detect(&message), Some(Format::Rustc));
+        assert_eq!(Format::detect(&serde_json::json!({ "reason": "x" })), None);
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(Format::parse("auto"), Some(None));
+        assert_eq!(Format::parse("clang"), Some(Some(Format::Gcc)));
+        assert_eq!(Format::parse("json"), None);
+    }
+
+    #[test]
+    fn value_streams() {
+        let values = values("[1, 2] 3\n{}").unwrap();
+        assert_eq!(values.len(), 4);
+
+        assert!(super::values("{\"level\": ").is_err());
+        assert!(super::values("[1, 2").is_err());
+    }
+
+    #[test]
+    fn malformed_diagnostics() {
+        let mut sources = Sources::new(TerminalReporter::default());
+        let cases = [
+            (
+                serde_json::json!({ "level": "fatal", "message": "" }),
+                Format::Nurse,
+            ),
+            (
+                serde_json::json!({ "message": "", "level": "error", "spans": [{}] }),
+                Format::Rustc,
+            ),
+            (serde_json::json!({ "kind": "error" }), Format::Gcc),
+            (
+                serde_json::json!({ "runs": [{ "results": [{}] }] }),
+                Format::Sarif,
+            ),
+        ];
+
+        for (value, format) in cases {
+            assert!(convert(value, format, &mut sources).is_err(), "{format:?}");
+        }
+    }
+}
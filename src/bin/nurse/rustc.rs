@@ -0,0 +1,127 @@
+//! Diagnostics emitted by `rustc --error-format=json`.
+
+use nurse::{prelude::*, Level};
+use serde::Deserialize;
+
+use crate::{diagnostic, sources::Sources};
+
+#[derive(Debug, Deserialize)]
+pub struct Message {
+    message: String,
+    code: Option<Code>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Code {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    is_primary: bool,
+    label: Option<String>,
+}
+
+fn level(level: &str) -> Level {
+    match level {
+        "warning" => Level::Warn,
+        "note" | "help" | "failure-note" => Level::Info,
+        _ => Level::Error,
+    }
+}
+
+impl Message {
+    /// Checks whether the message is one of rustc's closing summaries,
+    /// which are redundant with the reporter's own summary.
+    pub fn is_summary(&self) -> bool {
+        self.spans.is_empty()
+            && self.code.is_none()
+            && (self.message.starts_with("aborting due to")
+                || (self.level == "warning" && self.message.ends_with("emitted")))
+    }
+
+    pub fn convert(self, sources: &mut Sources) -> Diagnostic {
+        let mut converted =
+            diagnostic(level(&self.level), self.message).with_code(self.code.map(|code| code.code));
+
+        let primary = self.spans.iter().position(|span| span.is_primary);
+        for (i, span) in self.spans.into_iter().enumerate() {
+            let Some(converted_span) =
+                sources.span(&span.file_name, span.byte_start..span.byte_end)
+            else {
+                continue;
+            };
+
+            if Some(i) == primary {
+                converted.set_span(Some(converted_span));
+            }
+            if let Some(label) = span.label {
+                converted.add_spanned_note(label, converted_span);
+            }
+        }
+
+        for child in self.children {
+            let span = child
+                .spans
+                .iter()
+                .find(|span| span.is_primary)
+                .or(child.spans.first())
+                .and_then(|span| sources.span(&span.file_name, span.byte_start..span.byte_end));
+
+            match span {
+                Some(span) => converted.add_spanned_note(child.message, span),
+                None if child.level == "help" => converted.add_help(child.message),
+                None => converted.add_note(child.message),
+            };
+        }
+
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nurse::Level;
+
+    use crate::{
+        tests::{fixture, ranges},
+        Format,
+    };
+
+    #[test]
+    fn convert_fixture() {
+        let (_, diagnostics) = fixture("rustc.json", Format::Rustc);
+
+        // Build messages and the closing summary are skipped
+        assert_eq!(
+            ranges(&diagnostics),
+            [
+                ("literal out of range for `u8`", Some(28..31)),
+                ("unused variable: `x`", Some(20..21)),
+                ("span out of range", None),
+                ("in a missing file", None),
+            ]
+        );
+        assert_eq!(diagnostics[0].code(), Some("overflowing_literals"));
+        assert_eq!(diagnostics[1].level(), Level::Warn);
+
+        let notes = diagnostics[0].notes();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].span(), None);
+
+        let notes = diagnostics[1].notes();
+        assert_eq!(
+            notes[0].value(),
+            "if this is intentional, prefix it with an underscore"
+        );
+        assert_eq!(notes[0].span().map(|span| span.range()), Some(20..21));
+    }
+}
@@ -0,0 +1,179 @@
+//! Results of static analysis tools in the SARIF 2.1 format.
+
+use nurse::{prelude::*, Level};
+use serde::Deserialize;
+
+use crate::{
+    diagnostic,
+    sources::{Sources, Unit},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Log {
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Run {
+    #[serde(default)]
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: Option<String>,
+    level: Option<String>,
+    message: Message,
+    #[serde(default)]
+    locations: Vec<Location>,
+    #[serde(default)]
+    related_locations: Vec<Location>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: Option<PhysicalLocation>,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Option<Region>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    start_line: Option<usize>,
+    start_column: Option<usize>,
+    end_line: Option<usize>,
+    end_column: Option<usize>,
+    byte_offset: Option<usize>,
+    byte_length: Option<usize>,
+}
+
+fn level(level: Option<&str>) -> Level {
+    match level {
+        Some("error") => Level::Error,
+        Some("note" | "none") => Level::Info,
+        // Results are warnings unless stated otherwise
+        _ => Level::Warn,
+    }
+}
+
+impl Location {
+    fn span(&self, sources: &mut Sources) -> Option<Span> {
+        let physical = self.physical_location.as_ref()?;
+        let uri = &physical.artifact_location.uri;
+        let path = uri.strip_prefix("file://").unwrap_or(uri);
+        let region = physical.region.as_ref()?;
+
+        if let Some(start) = region.byte_offset {
+            return sources.span(path, start..start + region.byte_length.unwrap_or(0));
+        }
+
+        let start_line = region.start_line?;
+        let start = sources.location(
+            path,
+            start_line,
+            region.start_column.unwrap_or(1),
+            Unit::Chars,
+        )?;
+        // Regions without an end column extend to the end of their last line
+        let end_line = region.end_line.unwrap_or(start_line);
+        let end = match region.end_column {
+            Some(column) => sources.location(path, end_line, column, Unit::Chars)?,
+            None => sources.end_of_line(path, end_line)?,
+        };
+
+        sources.span_between(path, start, end)
+    }
+}
+
+impl Log {
+    pub fn convert(self, sources: &mut Sources) -> Vec<Diagnostic> {
+        self.runs
+            .into_iter()
+            .flat_map(|run| run.results)
+            .map(|result| result.convert(sources))
+            .collect()
+    }
+}
+
+impl SarifResult {
+    fn convert(self, sources: &mut Sources) -> Diagnostic {
+        let message = self.message.text.unwrap_or_default();
+        let mut converted =
+            diagnostic(level(self.level.as_deref()), message).with_code(self.rule_id);
+
+        // Only the first location is primary, the rest are treated like related locations
+        let primary = self.locations.first().map(|location| (true, location));
+        let related = self.locations.iter().skip(1).chain(&self.related_locations);
+
+        for (is_primary, location) in primary.into_iter().chain(related.map(|l| (false, l))) {
+            let Some(span) = location.span(sources) else {
+                continue;
+            };
+
+            if is_primary {
+                converted.set_span(Some(span));
+            }
+            if let Some(text) = location.message.as_ref().and_then(|m| m.text.as_ref()) {
+                converted.add_spanned_note(text.as_str(), span);
+            }
+        }
+
+        converted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nurse::Level;
+
+    use crate::{
+        tests::{fixture, ranges},
+        Format,
+    };
+
+    #[test]
+    fn convert_fixture() {
+        let (_, diagnostics) = fixture("sarif.json", Format::Sarif);
+
+        // Columns are counted in characters, and `é` is two bytes long
+        assert_eq!(
+            ranges(&diagnostics),
+            [
+                ("string literal assigned to an integer", Some(33..37)),
+                ("undeclared identifier", Some(50..51)),
+                ("line out of range", None),
+                ("column out of range", None),
+            ]
+        );
+
+        let levels: Vec<Level> = diagnostics.iter().map(|d| d.level()).collect();
+        assert_eq!(
+            levels,
+            [Level::Error, Level::Warn, Level::Info, Level::Info]
+        );
+
+        // Related locations without an end column extend to the end of their line
+        let note = &diagnostics[0].notes()[0];
+        assert_eq!(note.value(), "declared here");
+        assert_eq!(note.span().map(|span| span.range()), Some(25..38));
+    }
+}
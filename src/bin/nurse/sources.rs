@@ -0,0 +1,256 @@
+//! Loading of the source files referenced by diagnostics.
+
+use std::{collections::HashMap, fs, io::Stdout, ops::Range};
+
+use nurse::{prelude::*, Location, LookupKey};
+
+/// The unit that a tool counts columns in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Bytes,
+    Chars,
+}
+
+/// A source file read from disk and registered with the reporter.
+#[derive(Debug)]
+struct Source {
+    key: LookupKey,
+    text: String,
+}
+
+/// The source files referenced by diagnostics, read from disk as they are needed.
+#[derive(Debug)]
+pub struct Sources {
+    reporter: TerminalReporter<Stdout>,
+    files: HashMap<String, Option<Source>>,
+}
+
+impl Sources {
+    pub fn new(reporter: TerminalReporter<Stdout>) -> Sources {
+        Sources {
+            reporter,
+            files: HashMap::new(),
+        }
+    }
+
+    pub fn reporter(&self) -> &TerminalReporter<Stdout> {
+        &self.reporter
+    }
+
+    pub fn reporter_mut(&mut self) -> &mut TerminalReporter<Stdout> {
+        &mut self.reporter
+    }
+
+    pub fn into_reporter(self) -> TerminalReporter<Stdout> {
+        self.reporter
+    }
+
+    /// Gets the source file at `path`, reading and registering it the first time it is referenced.
+    ///
+    /// Files that can't be read are reported once with a warning,
+    /// and diagnostics referencing them are left without spans.
+    fn get(&mut self, path: &str) -> Option<&Source> {
+        if !self.files.contains_key(path) {
+            let source = match fs::read_to_string(path) {
+                Ok(text) => Some(Source {
+                    key: self.reporter.register_file(path, &text),
+                    text,
+                }),
+                Err(err) => {
+                    self.reporter.report(
+                        warning!("unable to read `{path}`: {err}")
+                            .with_note("diagnostics in this file will be shown without source"),
+                    );
+                    None
+                }
+            };
+
+            self.files.insert(path.to_owned(), source);
+        }
+
+        self.files[path].as_ref()
+    }
+
+    /// Gets the text of the given zero-indexed `line` in the file at `path`, excluding its line break.
+    fn line(&mut self, path: &str, line: usize) -> Option<&str> {
+        let key = self.get(path)?.key;
+        let head = self
+            .reporter
+            .offset(key, Location { line, column: 0 })
+            .ok()?;
+
+        let text = &self.files[path].as_ref()?.text[head..];
+        let text = text.split('\n').next().unwrap_or_default();
+        Some(text.trim_end_matches('\r'))
+    }
+
+    /// Creates a span covering the byte `range` in the file at `path`,
+    /// returning `None` if the file can't be read or the range doesn't lie within it.
+    pub fn span(&mut self, path: &str, range: Range<usize>) -> Option<Span> {
+        let source = self.get(path)?;
+        let valid = range.start <= range.end && source.text.get(range.clone()).is_some();
+
+        valid.then(|| Span::new(source.key, range))
+    }
+
+    /// Creates a span between two locations in the file at `path`,
+    /// returning `None` if the file can't be read or the locations don't form a span within it.
+    pub fn span_between(&mut self, path: &str, start: Location, end: Location) -> Option<Span> {
+        let key = self.get(path)?.key;
+        Span::from_locations(&self.reporter, key, start, end).ok()
+    }
+
+    /// Converts a one-indexed `line` and `column`, counted in `unit`s, into a location in the file at `path`,
+    /// returning `None` if the file can't be read or the position lies outside of it.
+    ///
+    /// The column may point one past the end of the line.
+    pub fn location(
+        &mut self,
+        path: &str,
+        line: usize,
+        column: usize,
+        unit: Unit,
+    ) -> Option<Location> {
+        let line = line.checked_sub(1)?;
+        let column = column.checked_sub(1)?;
+        let text = self.line(path, line)?;
+
+        let column = match unit {
+            Unit::Bytes => column,
+            Unit::Chars => text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([text.len()])
+                .nth(column)?,
+        };
+
+        let location = Location { line, column };
+        let key = self.get(path)?.key;
+        self.reporter.offset(key, location).ok()?;
+
+        Some(location)
+    }
+
+    /// Gets the location after the character at `location` in the file at `path`,
+    /// or `location` itself at the end of a line.
+    pub fn next_char(&mut self, path: &str, location: Location) -> Location {
+        let width = self
+            .line(path, location.line)
+            .and_then(|text| text.get(location.column..)?.chars().next())
+            .map_or(0, char::len_utf8);
+
+        Location {
+            column: location.column + width,
+            ..location
+        }
+    }
+
+    /// Gets the location of the end of the one-indexed `line` in the file at `path`, excluding its line break.
+    pub fn end_of_line(&mut self, path: &str, line: usize) -> Option<Location> {
+        let line = line.checked_sub(1)?;
+        let column = self.line(path, line)?.len();
+
+        Some(Location { line, column })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAIN: &str = "tests/fixtures/cli/main.c";
+
+    fn sources() -> Sources {
+        Sources::new(TerminalReporter::default())
+    }
+
+    #[test]
+    fn locations() {
+        let mut sources = sources();
+        let location = |line, column| Location { line, column };
+
+        // The second line is `    int y = 1 + "é";`, where `é` is two bytes long
+        assert_eq!(
+            sources.location(MAIN, 2, 18, Unit::Bytes),
+            Some(location(1, 17))
+        );
+        assert_eq!(
+            sources.location(MAIN, 2, 19, Unit::Chars),
+            Some(location(1, 19))
+        );
+        assert_eq!(
+            sources.location(MAIN, 2, 22, Unit::Bytes),
+            Some(location(1, 21))
+        );
+        assert_eq!(
+            sources.location(MAIN, 2, 21, Unit::Chars),
+            Some(location(1, 21))
+        );
+
+        // Inside of `é`
+        assert_eq!(sources.location(MAIN, 2, 19, Unit::Bytes), None);
+
+        assert_eq!(sources.location(MAIN, 2, 23, Unit::Bytes), None);
+        assert_eq!(sources.location(MAIN, 2, 22, Unit::Chars), None);
+        assert_eq!(sources.location(MAIN, 9, 1, Unit::Bytes), None);
+        assert_eq!(sources.location(MAIN, 0, 1, Unit::Bytes), None);
+        assert_eq!(sources.location(MAIN, 1, 0, Unit::Bytes), None);
+    }
+
+    #[test]
+    fn line_ends() {
+        let mut sources = sources();
+
+        let before = Location {
+            line: 1,
+            column: 17,
+        };
+        assert_eq!(sources.next_char(MAIN, before).column, 19);
+        let end = Location {
+            line: 1,
+            column: 21,
+        };
+        assert_eq!(sources.next_char(MAIN, end), end);
+
+        assert_eq!(sources.end_of_line(MAIN, 2), Some(end));
+        assert_eq!(sources.end_of_line(MAIN, 9), None);
+    }
+
+    #[test]
+    fn spans() {
+        let mut sources = sources();
+
+        assert!(sources.span(MAIN, 29..37).is_some());
+        assert_eq!(sources.span(MAIN, 34..35), None);
+        #[allow(clippy::reversed_empty_ranges)]
+        let inverted = 37..29;
+        assert_eq!(sources.span(MAIN, inverted), None);
+        assert_eq!(sources.span(MAIN, 50..100), None);
+
+        let start = Location {
+            line: 1,
+            column: 12,
+        };
+        let end = Location {
+            line: 1,
+            column: 20,
+        };
+        let span = sources.span_between(MAIN, start, end).unwrap();
+        assert_eq!(span.range(), 29..37);
+        assert_eq!(sources.span_between(MAIN, end, start), None);
+    }
+
+    #[test]
+    fn missing_file() {
+        let mut sources = sources();
+
+        assert_eq!(sources.span("tests/fixtures/cli/missing.c", 0..0), None);
+        assert_eq!(
+            sources.location("tests/fixtures/cli/missing.c", 1, 1, Unit::Bytes),
+            None
+        );
+
+        // The unreadable file is reported as a warning
+        assert!(!sources.reporter().is_empty());
+    }
+}
@@ -1,4 +1,12 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+};
 
 use crate::span::Span;
 use concat_idents::concat_idents;
@@ -6,13 +14,33 @@ use concat_idents::concat_idents;
 use colored::{Color, ColoredString, Colorize};
 
 /// A diagnostic message ready to be output.
+///
+/// Two diagnostics are equal when their levels, messages, spans, notes, lints, and codes are all equal.
+/// The [source](Error::source) of a diagnostic is not compared.
+///
+/// With the `serde` feature, diagnostics can be serialized and deserialized,
+/// with the exception of their source, which is skipped.
 #[must_use = "Diagnostics should either be emitted or reported!"]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Diagnostic {
     pub(crate) level: Level,
     pub(crate) message: String,
-    pub(crate) note: Option<Note>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) notes: Vec<Note>,
     pub(crate) span: Option<Span>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) lint: Option<&'static str>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) code: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) source: Option<Arc<dyn Error + Send + Sync + 'static>>,
 }
 
 macro_rules! diagnostic_level {
@@ -79,8 +107,11 @@ macro_rules! diagnostic_level {
                 Diagnostic {
                     level: Level::$variant,
                     message: message.into(),
-                    note: None,
+                    notes: Vec::new(),
                     span: None,
+                    lint: None,
+                    code: None,
+                    source: None,
                 }
             }
 
@@ -90,8 +121,11 @@ macro_rules! diagnostic_level {
                     Diagnostic {
                         level: Level::$variant,
                         message: message.into(),
-                        note: None,
+                        notes: Vec::new(),
                         span: Some(span),
+                        lint: None,
+                        code: None,
+                        source: None,
                     }
                 }
             }}
@@ -147,36 +181,159 @@ impl Diagnostic {
         self
     }
 
-    /// Sets the optional note of the diagnostic.
+    /// Sets the note of the diagnostic, replacing any notes it already has.
+    ///
+    /// Use [`add_note`](Diagnostic::add_note) to attach several notes.
     pub fn set_note<S: Into<Note>>(&mut self, note: S) -> &mut Diagnostic {
-        self.note = Some(note.into());
+        self.notes = vec![note.into()];
         self
     }
 
-    /// Sets the optional note of the diagnostic.
+    /// Sets the note of the diagnostic, replacing any notes it already has.
     #[inline]
     pub fn with_note<S: Into<Note>>(mut self, note: S) -> Diagnostic {
         self.set_note(note);
         self
     }
 
-    /// Sets the optional note of the diagnostic with the given span.
+    /// Adds a note to the diagnostic, after any notes it already has.
+    pub fn add_note<S: Into<Note>>(&mut self, note: S) -> &mut Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Adds notes to the diagnostic, after any notes it already has.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::{prelude::*, Note};
+    /// # let mut reporter = TerminalReporter::default();
+    /// # let file = reporter.register_file("example.txt", "let x = 1;");
+    /// let diagnostic = error!("unused variable `x`").with_notes([
+    ///     Note::spanned(Span::new(file, 4..5), "defined here"),
+    ///     Note::from("prefix it with an underscore to silence this warning"),
+    /// ]);
+    ///
+    /// assert_eq!(diagnostic.notes().len(), 2);
+    /// ```
     #[inline]
-    pub fn set_spanned_note<S: Into<String>>(&mut self, note: S, span: Span) -> &mut Diagnostic {
-        self.set_note(Note {
-            value: note.into(),
-            span: Some(span),
-        });
+    pub fn with_notes<I: IntoIterator<Item = S>, S: Into<Note>>(mut self, notes: I) -> Diagnostic {
+        self.notes.extend(notes.into_iter().map(Into::into));
         self
     }
 
-    /// Sets the optional note of the diagnostic with the given span.
+    /// Sets the note of the diagnostic with the given span, replacing any notes it already has.
+    ///
+    /// Spanned notes in the same file as the diagnostic
+    /// are printed as labels in the diagnostic's snippet.
+    #[inline]
+    pub fn set_spanned_note<S: Into<String>>(&mut self, note: S, span: Span) -> &mut Diagnostic {
+        self.set_note(Note::spanned(span, note))
+    }
+
+    /// Sets the note of the diagnostic with the given span, replacing any notes it already has.
+    ///
+    /// Spanned notes in the same file as the diagnostic
+    /// are printed as labels in the diagnostic's snippet.
     #[inline]
     pub fn with_spanned_note<S: Into<String>>(mut self, span: Span, note: S) -> Diagnostic {
         self.set_spanned_note(note, span);
         self
     }
 
+    /// Adds a note with the given span to the diagnostic, after any notes it already has.
+    #[inline]
+    pub fn add_spanned_note<S: Into<String>>(&mut self, note: S, span: Span) -> &mut Diagnostic {
+        self.add_note(Note::spanned(span, note))
+    }
+
+    /// Adds a help message to the diagnostic,
+    /// suggesting how to fix the problem.
+    ///
+    /// Help messages are stored alongside notes, after any notes the diagnostic already has,
+    /// and printed as `= help: ...`.
+    pub fn add_help<S: Into<String>>(&mut self, help: S) -> &mut Diagnostic {
+        self.add_note(Note {
+            value: help.into(),
+            span: None,
+            help: true,
+        })
+    }
+
+    /// Adds a help message to the diagnostic,
+    /// suggesting how to fix the problem.
+    #[inline]
+    pub fn with_help<S: Into<String>>(mut self, help: S) -> Diagnostic {
+        self.add_help(help);
+        self
+    }
+
+    /// Gets the notes associated with the diagnostic, in the order they were added.
+    #[inline]
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    /// Gets the name of the [`Lint`] that produced the diagnostic, if any.
+    #[inline]
+    pub fn lint(&self) -> Option<&'static str> {
+        self.lint
+    }
+
+    /// Sets the name of the [`Lint`] that produced the diagnostic.
+    ///
+    /// Diagnostics produced by a lint can be suppressed by comments in the source,
+    /// see [`Suppressions`](crate::Suppressions).
+    #[inline]
+    pub fn set_lint(&mut self, lint: Option<&'static str>) -> &mut Diagnostic {
+        self.lint = lint;
+        self
+    }
+
+    /// Sets the name of the [`Lint`] that produced the diagnostic.
+    #[inline]
+    pub fn with_lint(mut self, lint: Option<&'static str>) -> Diagnostic {
+        self.set_lint(lint);
+        self
+    }
+
+    /// Gets the code identifying the kind of diagnostic, such as `E0308`, if any.
+    #[inline]
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// Sets the code identifying the kind of diagnostic,
+    /// printed after its level, e.g. `error[E0308]: mismatched types`.
+    #[inline]
+    pub fn set_code<S: Into<String>>(&mut self, code: Option<S>) -> &mut Diagnostic {
+        self.code = code.map(Into::into);
+        self
+    }
+
+    /// Sets the code identifying the kind of diagnostic.
+    #[inline]
+    pub fn with_code<S: Into<String>>(mut self, code: Option<S>) -> Diagnostic {
+        self.set_code(code);
+        self
+    }
+
+    /// Sets the error that caused the diagnostic,
+    /// returned by [`Error::source`].
+    pub fn set_source<E: Error + Send + Sync + 'static>(&mut self, source: E) -> &mut Diagnostic {
+        self.source = Some(Arc::new(source));
+        self
+    }
+
+    /// Sets the error that caused the diagnostic,
+    /// returned by [`Error::source`].
+    #[inline]
+    pub fn with_source<E: Error + Send + Sync + 'static>(mut self, source: E) -> Diagnostic {
+        self.set_source(source);
+        self
+    }
+
     /// Gets the [`Level`] associated with the diagnostic.
     #[inline]
     pub fn level(&self) -> Level {
@@ -189,9 +346,16 @@ impl Diagnostic {
         self.level == Level::Error
     }
 
-    pub(crate) fn format_message(&self) -> ColoredString {
-        let title = self.level.title();
-        let color = self.level.color();
+    /// The level of the diagnostic followed by its code, e.g. `error[E0308]`.
+    pub(crate) fn title(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{code}]", self.level.title()),
+            None => self.level.title().to_owned(),
+        }
+    }
+
+    pub(crate) fn format_message(&self, color: Color) -> ColoredString {
+        let title = self.title();
 
         let formatted = format!("{}: {}", title.color(color).bold(), self.message);
         if self.span.is_some() {
@@ -204,46 +368,218 @@ impl Diagnostic {
 
 impl PartialEq for Diagnostic {
     fn eq(&self, other: &Diagnostic) -> bool {
-        self.message == other.message && self.level == other.level
+        self.level == other.level
+            && self.message == other.message
+            && self.notes == other.notes
+            && self.span == other.span
+            && self.lint == other.lint
+            && self.code == other.code
     }
 }
 
-/// An optional note to be associated with a [`Diagnostic`].
-#[derive(Debug, Clone)]
+impl Eq for Diagnostic {}
+
+impl Hash for Diagnostic {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.level.hash(state);
+        self.message.hash(state);
+        self.notes.hash(state);
+        self.span.hash(state);
+        self.lint.hash(state);
+        self.code.hash(state);
+    }
+}
+
+/// Formats the level, message, and notes of the diagnostic as plain text,
+/// without any source snippets.
+///
+/// ```rust
+/// use nurse::prelude::*;
+///
+/// let diagnostic = error!("unexpected token").with_note("expected `)`");
+/// assert_eq!(diagnostic.to_string(), "error: unexpected token\n = note: expected `)`");
+/// ```
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.title(), self.message)?;
+        for note in &self.notes {
+            write!(f, "\n = {}: {}", note.title(), note.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for Diagnostic {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn Error + 'static))
+    }
+}
+
+/// Converts errors into [`Error`](Level::Error) level diagnostics.
+///
+/// The message of the diagnostic is the error's message,
+/// with a note for each error in its [`source`](Error::source) chain.
+/// The error itself becomes the source of the diagnostic.
+/// Since diagnostics are errors themselves, a [`Diagnostic`] is returned unchanged.
+///
+/// ## Example
+///
+/// ```rust
+/// use nurse::IntoDiagnostic;
+///
+/// let error = std::io::Error::new(std::io::ErrorKind::NotFound, "config.toml not found");
+/// let diagnostic = error.into_diagnostic();
+///
+/// assert_eq!(diagnostic.message(), "config.toml not found");
+/// ```
+pub trait IntoDiagnostic {
+    /// Converts the error into a diagnostic.
+    fn into_diagnostic(self) -> Diagnostic;
+}
+
+impl<E: Error + Send + Sync + 'static> IntoDiagnostic for E {
+    fn into_diagnostic(self) -> Diagnostic {
+        let error: Box<dyn Error + Send + Sync> = Box::new(self);
+        let error = match error.downcast::<Diagnostic>() {
+            Ok(diagnostic) => return *diagnostic,
+            Err(error) => error,
+        };
+
+        let mut diagnostic = Diagnostic::error(error.to_string());
+
+        let mut cause = error.source();
+        while let Some(error) = cause {
+            diagnostic.add_note(format!("caused by: {error}"));
+            cause = error.source();
+        }
+
+        diagnostic.source = Some(Arc::from(error));
+        diagnostic
+    }
+}
+
+/// Converts the errors of results into diagnostics using [`IntoDiagnostic`],
+/// allowing `?` to be used in functions returning diagnostics.
+///
+/// ## Example
+///
+/// ```rust
+/// use nurse::{prelude::*, ResultExt};
+///
+/// fn read_config(path: &str) -> Result<String, Diagnostic> {
+///     let config = std::fs::read_to_string(path).into_diagnostic()?;
+///     Ok(config)
+/// }
+///
+/// let diagnostic = read_config("missing.toml").unwrap_err();
+/// assert!(diagnostic.is_error());
+/// ```
+pub trait ResultExt<T> {
+    /// Converts the error of the result into a diagnostic.
+    #[allow(clippy::result_large_err)]
+    fn into_diagnostic(self) -> Result<T, Diagnostic>;
+}
+
+impl<T, E: Error + Send + Sync + 'static> ResultExt<T> for Result<T, E> {
+    #[inline]
+    #[allow(clippy::result_large_err)]
+    fn into_diagnostic(self) -> Result<T, Diagnostic> {
+        self.map_err(IntoDiagnostic::into_diagnostic)
+    }
+}
+
+/// A note to be associated with a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Note {
     pub(crate) value: String,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub(crate) span: Option<Span>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) help: bool,
 }
 
-impl Into<Note> for String {
-    fn into(self) -> Note {
+impl Note {
+    /// Creates a note with the given span.
+    #[inline]
+    pub fn spanned<S: Into<String>>(span: Span, value: S) -> Note {
         Note {
-            value: self,
+            value: value.into(),
+            span: Some(span),
+            help: false,
+        }
+    }
+
+    /// Gets the message of the note.
+    #[inline]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Gets the span of the note if it exists,
+    /// returning `None` if there is not.
+    #[inline]
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// Checks whether the note is a help message,
+    /// added with [`add_help`](Diagnostic::add_help).
+    #[inline]
+    pub fn is_help(&self) -> bool {
+        self.help
+    }
+
+    #[inline]
+    pub(crate) fn title(&self) -> &'static str {
+        if self.help {
+            "help"
+        } else {
+            "note"
+        }
+    }
+}
+
+impl From<String> for Note {
+    fn from(value: String) -> Note {
+        Note {
+            value,
             span: None,
+            help: false,
         }
     }
 }
 
-impl Into<Note> for &str {
-    fn into(self) -> Note {
+impl From<&str> for Note {
+    fn from(value: &str) -> Note {
         Note {
-            value: self.to_owned(),
+            value: value.to_owned(),
             span: None,
+            help: false,
         }
     }
 }
 
-impl Into<Note> for Cow<'_, str> {
-    fn into(self) -> Note {
+impl From<Cow<'_, str>> for Note {
+    fn from(value: Cow<'_, str>) -> Note {
         Note {
-            value: self.into_owned(),
+            value: value.into_owned(),
             span: None,
+            help: false,
         }
     }
 }
 
 /// The level of a diagnostic.
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Level {
     /// The "error" level.
     ///
@@ -265,6 +601,12 @@ pub enum Level {
 }
 
 impl Level {
+    /// The position of the level in per-level tables.
+    #[inline]
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+
     pub(crate) fn title(&self) -> &'static str {
         match self {
             Level::Error => "error",
@@ -273,19 +615,15 @@ impl Level {
             Level::Debug => "debug",
         }
     }
-
-    pub(crate) fn color(&self) -> Color {
-        match self {
-            Level::Error => Color::BrightRed,
-            Level::Warn => Color::BrightYellow,
-            Level::Info => Color::Cyan,
-            Level::Debug => Color::BrightMagenta,
-        }
-    }
 }
 
 /// The filter for which level diagnostics are allowed.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum LevelFilter {
     /// Disables all diagnostics
     Off,
@@ -301,12 +639,573 @@ pub enum LevelFilter {
 
 impl LevelFilter {
     pub(crate) fn passes(&self, level: Level) -> bool {
-        match (self, level) {
-            (LevelFilter::Error, Level::Error) => true,
-            (LevelFilter::Warn, Level::Error | Level::Warn) => true,
-            (LevelFilter::Info, Level::Error | Level::Warn | Level::Info) => true,
-            (LevelFilter::Debug, _) => true,
-            _ => false,
+        matches!(
+            (self, level),
+            (LevelFilter::Error, Level::Error)
+                | (LevelFilter::Warn, Level::Error | Level::Warn)
+                | (LevelFilter::Info, Level::Error | Level::Warn | Level::Info)
+                | (LevelFilter::Debug, _)
+        )
+    }
+}
+
+/// A policy changing the level of diagnostics as they are reported,
+/// such as treating warnings as errors.
+///
+/// Unlike a [`LevelFilter`], which can only hide diagnostics,
+/// a policy can promote diagnostics to a more severe level or demote them to a less severe one.
+///
+/// ## Example
+///
+/// ```rust
+/// use nurse::prelude::*;
+/// use nurse::{Level, LevelPolicy};
+///
+/// let policy = LevelPolicy::warnings_as_errors("`-D warnings` is enabled");
+/// let diagnostic = policy.apply(warning!("unused label"));
+///
+/// assert_eq!(diagnostic.level(), Level::Error);
+/// assert_eq!(diagnostic.notes()[0].value(), "`-D warnings` is enabled");
+/// ```
+///
+/// Specific categories of diagnostics can be remapped by their [lint](Diagnostic::lint) or [code](Diagnostic::code).
+/// A diagnostic's lint mapping takes precedence over its code mapping,
+/// which takes precedence over the mapping of its level,
+/// and only the first of these to apply changes the diagnostic.
+///
+/// ```rust
+/// use nurse::prelude::*;
+/// use nurse::{Level, LevelPolicy, Lint, LintLevel};
+///
+/// const DEAD_CODE: Lint = Lint::new("dead_code", LintLevel::Warn);
+///
+/// let policy = LevelPolicy::warnings_as_errors("`-D warnings` is enabled")
+///     .remap_lint(&DEAD_CODE, Level::Info)
+///     .remap_code("W0102", Level::Info);
+///
+/// let lint = warning!("unused function `f`").with_lint(Some(DEAD_CODE.name()));
+/// let coded = warning!("unused import").with_code(Some("W0102"));
+///
+/// assert_eq!(policy.apply(lint).level(), Level::Info);
+/// assert_eq!(policy.apply(coded).level(), Level::Info);
+/// assert_eq!(policy.apply(warning!("unused label")).level(), Level::Error);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LevelPolicy {
+    overrides: [Option<(Level, Option<String>)>; 4],
+    lints: HashMap<&'static str, (Level, Option<String>)>,
+    codes: HashMap<String, (Level, Option<String>)>,
+}
+
+impl LevelPolicy {
+    /// Creates a policy that leaves every level unchanged.
+    #[inline]
+    pub fn new() -> LevelPolicy {
+        LevelPolicy::default()
+    }
+
+    /// Creates a policy promoting [`Warn`](Level::Warn) level diagnostics to [`Error`](Level::Error),
+    /// attaching `note` to each promoted diagnostic to explain why.
+    pub fn warnings_as_errors<S: Into<String>>(note: S) -> LevelPolicy {
+        LevelPolicy::new().remap_with_note(Level::Warn, Level::Error, note)
+    }
+
+    /// Changes diagnostics of level `from` to level `to`.
+    ///
+    /// Replaces any previous mapping of `from`.
+    pub fn remap(mut self, from: Level, to: Level) -> LevelPolicy {
+        self.overrides[from.index()] = Some((to, None));
+        self
+    }
+
+    /// Changes diagnostics of level `from` to level `to`,
+    /// attaching `note` to each changed diagnostic to explain why.
+    ///
+    /// Replaces any previous mapping of `from`.
+    pub fn remap_with_note<S: Into<String>>(
+        mut self,
+        from: Level,
+        to: Level,
+        note: S,
+    ) -> LevelPolicy {
+        self.overrides[from.index()] = Some((to, Some(note.into())));
+        self
+    }
+
+    /// Changes diagnostics produced by `lint` to level `to`, whatever their level.
+    ///
+    /// Replaces any previous mapping of `lint`.
+    pub fn remap_lint(mut self, lint: &Lint, to: Level) -> LevelPolicy {
+        self.lints.insert(lint.name, (to, None));
+        self
+    }
+
+    /// Changes diagnostics produced by `lint` to level `to`, whatever their level,
+    /// attaching `note` to each changed diagnostic to explain why.
+    ///
+    /// Replaces any previous mapping of `lint`.
+    pub fn remap_lint_with_note<S: Into<String>>(
+        mut self,
+        lint: &Lint,
+        to: Level,
+        note: S,
+    ) -> LevelPolicy {
+        self.lints.insert(lint.name, (to, Some(note.into())));
+        self
+    }
+
+    /// Changes diagnostics with the given [code](Diagnostic::code) to level `to`, whatever their level.
+    ///
+    /// Replaces any previous mapping of `code`.
+    pub fn remap_code<S: Into<String>>(mut self, code: S, to: Level) -> LevelPolicy {
+        self.codes.insert(code.into(), (to, None));
+        self
+    }
+
+    /// Changes diagnostics with the given [code](Diagnostic::code) to level `to`, whatever their level,
+    /// attaching `note` to each changed diagnostic to explain why.
+    ///
+    /// Replaces any previous mapping of `code`.
+    pub fn remap_code_with_note<C: Into<String>, S: Into<String>>(
+        mut self,
+        code: C,
+        to: Level,
+        note: S,
+    ) -> LevelPolicy {
+        self.codes.insert(code.into(), (to, Some(note.into())));
+        self
+    }
+
+    /// Gets the level diagnostics of level `level` are changed to,
+    /// ignoring any mappings of lints and codes.
+    #[inline]
+    pub fn level(&self, level: Level) -> Level {
+        self.overrides[level.index()]
+            .as_ref()
+            .map_or(level, |&(level, _)| level)
+    }
+
+    /// Applies the policy to `diagnostic`, returning the changed diagnostic.
+    pub fn apply(&self, mut diagnostic: Diagnostic) -> Diagnostic {
+        let mapping = diagnostic
+            .lint
+            .and_then(|lint| self.lints.get(lint))
+            .or_else(|| {
+                diagnostic
+                    .code
+                    .as_ref()
+                    .and_then(|code| self.codes.get(code))
+            })
+            .or(self.overrides[diagnostic.level.index()].as_ref());
+
+        if let Some((level, note)) = mapping {
+            diagnostic.level = *level;
+            if let Some(note) = note {
+                diagnostic.add_note(note.as_str());
+            }
+        }
+
+        diagnostic
+    }
+}
+
+/// The level a [`Lint`] is reported at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintLevel {
+    /// The lint is not reported.
+    Allow,
+    /// The lint is reported as a [`Warn`](Level::Warn) level diagnostic.
+    Warn,
+    /// The lint is reported as an [`Error`](Level::Error) level diagnostic.
+    Deny,
+    /// The lint is reported as an [`Error`](Level::Error) level diagnostic,
+    /// and its level can no longer be changed.
+    Forbid,
+}
+
+impl LintLevel {
+    /// Gets the level of diagnostics reported for lints of this level,
+    /// returning `None` for [`Allow`](LintLevel::Allow).
+    pub fn level(&self) -> Option<Level> {
+        match self {
+            LintLevel::Allow => None,
+            LintLevel::Warn => Some(Level::Warn),
+            LintLevel::Deny | LintLevel::Forbid => Some(Level::Error),
+        }
+    }
+
+    /// Gets the name of the level, as accepted by [`from_str`](LintLevel::from_str).
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+}
+
+impl FromStr for LintLevel {
+    type Err = LintError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(LintLevel::Allow),
+            "warn" => Ok(LintLevel::Warn),
+            "deny" => Ok(LintLevel::Deny),
+            "forbid" => Ok(LintLevel::Forbid),
+            _ => Err(LintError::UnknownLevel(s.to_owned())),
+        }
+    }
+}
+
+/// A named check that can be enabled or disabled by users, such as `unused_label`.
+///
+/// Lints are usually declared as constants and registered with a [`LintRegistry`].
+///
+/// ```rust
+/// use nurse::{Lint, LintLevel};
+///
+/// const UNUSED_LABEL: Lint = Lint::new("unused_label", LintLevel::Warn);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lint {
+    name: &'static str,
+    default: LintLevel,
+}
+
+impl Lint {
+    /// Creates a new lint with the given name and default level.
+    pub const fn new(name: &'static str, default: LintLevel) -> Lint {
+        Lint { name, default }
+    }
+
+    /// Gets the name of the lint.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Gets the level the lint is reported at unless overridden.
+    #[inline]
+    pub fn default_level(&self) -> LintLevel {
+        self.default
+    }
+}
+
+/// A collection of [`Lint`]s and the levels they are reported at.
+///
+/// ## Example
+///
+/// ```rust
+/// use nurse::{Level, Lint, LintLevel, LintRegistry};
+///
+/// const UNUSED_LABEL: Lint = Lint::new("unused_label", LintLevel::Warn);
+///
+/// let mut lints = LintRegistry::new();
+/// lints.register(UNUSED_LABEL);
+///
+/// // e.g. parsed from `-D unused_label` on the command line
+/// lints.set("unused_label", LintLevel::Deny, "`-D unused_label`").unwrap();
+///
+/// let diagnostic = lints.lint(&UNUSED_LABEL, "unused label `loop`").unwrap();
+/// assert_eq!(diagnostic.level(), Level::Error);
+/// assert_eq!(
+///     diagnostic.notes()[0].value(),
+///     "`unused_label` is set to `deny` by `-D unused_label`"
+/// );
+///
+/// lints.set("unused_label", LintLevel::Allow, "`-A unused_label`").unwrap();
+/// assert!(lints.lint(&UNUSED_LABEL, "unused label `loop`").is_none());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintRegistry {
+    lints: HashMap<&'static str, Lint>,
+    overrides: HashMap<&'static str, (LintLevel, String)>,
+}
+
+impl LintRegistry {
+    /// Creates an empty registry.
+    #[inline]
+    pub fn new() -> LintRegistry {
+        LintRegistry::default()
+    }
+
+    /// Adds `lint` to the registry, allowing its level to be overridden by name.
+    pub fn register(&mut self, lint: Lint) {
+        self.lints.insert(lint.name, lint);
+    }
+
+    /// Gets the registered lint with the given name.
+    pub fn get(&self, name: &str) -> Option<Lint> {
+        self.lints.get(name).copied()
+    }
+
+    /// Overrides the level of the lint called `name`.
+    ///
+    /// `source` describes where the override came from, such as a command-line flag or a configuration file,
+    /// and is included in the notes of reported lints.
+    ///
+    /// Returns an error if no lint called `name` is registered,
+    /// or if the lint has been set to [`Forbid`](LintLevel::Forbid) and `level` is any other level.
+    pub fn set<S: Into<String>>(
+        &mut self,
+        name: &str,
+        level: LintLevel,
+        source: S,
+    ) -> Result<(), LintError> {
+        let lint = self
+            .get(name)
+            .ok_or_else(|| LintError::UnknownLint(name.to_owned()))?;
+
+        if self.level(&lint) == LintLevel::Forbid && level != LintLevel::Forbid {
+            return Err(LintError::Forbidden {
+                lint: lint.name,
+                level,
+            });
+        }
+
+        self.overrides.insert(lint.name, (level, source.into()));
+        Ok(())
+    }
+
+    /// Gets the level `lint` is reported at.
+    pub fn level(&self, lint: &Lint) -> LintLevel {
+        self.overrides
+            .get(lint.name)
+            .map_or(lint.default, |&(level, _)| level)
+    }
+
+    /// Creates a diagnostic reporting `lint` at its configured level,
+    /// returning `None` if the lint is allowed.
+    ///
+    /// The diagnostic has a note explaining why the lint is reported at its level.
+    pub fn lint<S: Into<String>>(&self, lint: &Lint, message: S) -> Option<Diagnostic> {
+        let (level, note) = match self.overrides.get(lint.name) {
+            Some((level, source)) => (
+                *level,
+                format!("`{}` is set to `{}` by {source}", lint.name, level.name()),
+            ),
+            None => (
+                lint.default,
+                format!(
+                    "`{}` is set to `{}` by default",
+                    lint.name,
+                    lint.default.name()
+                ),
+            ),
+        };
+
+        let mut diagnostic = match level.level()? {
+            Level::Error => Diagnostic::error(message),
+            _ => Diagnostic::warning(message),
+        };
+        diagnostic.set_note(note).set_lint(Some(lint.name));
+
+        Some(diagnostic)
+    }
+}
+
+/// An error produced when configuring a [`LintRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintError {
+    /// No lint with the given name is registered.
+    UnknownLint(String),
+    /// The given string is not the name of a [`LintLevel`].
+    UnknownLevel(String),
+    /// The lint has been set to [`Forbid`](LintLevel::Forbid),
+    /// and cannot be changed to another level.
+    Forbidden {
+        /// The name of the forbidden lint
+        lint: &'static str,
+        /// The level the lint was attempted to be set to
+        level: LintLevel,
+    },
+}
+
+impl fmt::Display for LintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintError::UnknownLint(name) => write!(f, "unknown lint `{name}`"),
+            LintError::UnknownLevel(level) => write!(f, "unknown lint level `{level}`"),
+            LintError::Forbidden { lint, level } => write!(
+                f,
+                "`{}` incompatible with previous forbid of `{lint}`",
+                level.name()
+            ),
+        }
+    }
+}
+
+impl Error for LintError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_note_replaces_and_add_note_appends() {
+        let mut diagnostic = Diagnostic::error("mismatched types")
+            .with_note("first")
+            .with_note("second");
+        assert_eq!(diagnostic.notes().len(), 1);
+        assert_eq!(diagnostic.notes()[0].value(), "second");
+
+        diagnostic.add_note("third").add_help("try a cast");
+        let notes: Vec<&str> = diagnostic.notes().iter().map(Note::value).collect();
+        assert_eq!(notes, ["second", "third", "try a cast"]);
+
+        diagnostic.set_note("only");
+        assert_eq!(diagnostic.notes().len(), 1);
+    }
+
+    #[test]
+    fn diagnostics_convert_unchanged() {
+        let diagnostic = Diagnostic::warning("unused label")
+            .with_note("labels must be jumped to")
+            .with_lint(Some("unused_label"));
+
+        let converted = Err::<(), _>(diagnostic.clone()).into_diagnostic();
+        assert_eq!(converted, Err(diagnostic));
+
+        let error = std::io::Error::new(std::io::ErrorKind::NotFound, "config.toml not found");
+        let converted = error.into_diagnostic();
+        assert_eq!(converted.level(), Level::Error);
+        assert!(converted.source().is_some());
+    }
+
+    #[test]
+    fn policy_precedence() {
+        const UNUSED: Lint = Lint::new("unused", LintLevel::Warn);
+
+        let policy = LevelPolicy::new()
+            .remap(Level::Warn, Level::Error)
+            .remap_code_with_note("W1", Level::Debug, "code")
+            .remap_lint_with_note(&UNUSED, Level::Info, "lint");
+
+        let both = Diagnostic::warning("both")
+            .with_lint(Some(UNUSED.name()))
+            .with_code(Some("W1"));
+        let both = policy.apply(both);
+        assert_eq!(both.level(), Level::Info);
+        assert_eq!(both.notes()[0].value(), "lint");
+
+        let code = policy.apply(Diagnostic::error("code").with_code(Some("W1")));
+        assert_eq!(code.level(), Level::Debug);
+        assert_eq!(code.notes()[0].value(), "code");
+
+        let other = policy.apply(Diagnostic::warning("other").with_code(Some("W2")));
+        assert_eq!(other.level(), Level::Error);
+        assert!(other.notes().is_empty());
+    }
+
+    #[test]
+    fn forbidden_lints_cannot_be_overridden() {
+        const UNUSED: Lint = Lint::new("unused", LintLevel::Warn);
+        const UNSAFE: Lint = Lint::new("unsafe", LintLevel::Forbid);
+
+        let mut lints = LintRegistry::new();
+        lints.register(UNUSED);
+        lints.register(UNSAFE);
+
+        lints
+            .set("unused", LintLevel::Forbid, "`-F unused`")
+            .unwrap();
+        assert_eq!(
+            lints.set("unused", LintLevel::Allow, "`-A unused`"),
+            Err(LintError::Forbidden {
+                lint: "unused",
+                level: LintLevel::Allow
+            })
+        );
+        assert_eq!(
+            lints.set("unsafe", LintLevel::Warn, "`-W unsafe`"),
+            Err(LintError::Forbidden {
+                lint: "unsafe",
+                level: LintLevel::Warn
+            })
+        );
+        assert_eq!(lints.level(&UNUSED), LintLevel::Forbid);
+        assert_eq!(lints.level(&UNSAFE), LintLevel::Forbid);
+
+        // Forbidding a forbidden lint again is allowed
+        lints
+            .set("unsafe", LintLevel::Forbid, "`-F unsafe`")
+            .unwrap();
+
+        let diagnostic = lints.lint(&UNUSED, "unused label").unwrap();
+        assert_eq!(diagnostic.level(), Level::Error);
+        assert_eq!(
+            diagnostic.notes()[0].value(),
+            "`unused` is set to `forbid` by `-F unused`"
+        );
+        assert_eq!(
+            LintError::Forbidden {
+                lint: "unused",
+                level: LintLevel::Allow
+            }
+            .to_string(),
+            "`allow` incompatible with previous forbid of `unused`"
+        );
+    }
+
+    #[test]
+    fn unknown_lints_and_levels() {
+        const UNUSED: Lint = Lint::new("unused", LintLevel::Warn);
+
+        let mut lints = LintRegistry::new();
+        assert_eq!(
+            lints.set("unused", LintLevel::Deny, "`-D unused`"),
+            Err(LintError::UnknownLint("unused".to_owned()))
+        );
+
+        // Unregistered lints are still reported at their default level
+        assert_eq!(lints.level(&UNUSED), LintLevel::Warn);
+
+        lints.register(UNUSED);
+        assert_eq!(lints.get("unused"), Some(UNUSED));
+        assert_eq!(lints.get("unsafe"), None);
+
+        assert_eq!("deny".parse(), Ok(LintLevel::Deny));
+        assert_eq!(
+            "loud".parse::<LintLevel>(),
+            Err(LintError::UnknownLevel("loud".to_owned()))
+        );
+        assert_eq!(
+            LintError::UnknownLint("unsafe".to_owned()).to_string(),
+            "unknown lint `unsafe`"
+        );
+    }
+
+    #[test]
+    fn equality_covers_every_field() {
+        use std::collections::hash_map::DefaultHasher;
+
+        use crate::reporter::LookupKey;
+
+        fn hash(diagnostic: &Diagnostic) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            diagnostic.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let key = LookupKey::default();
+        let base = Diagnostic::spanned_error(Span::new(key, 0..1), "unmatched parenthesis")
+            .with_note("expected `)`");
+        assert_eq!(base, base.clone());
+        assert_eq!(hash(&base), hash(&base.clone()));
+
+        let variants = [
+            base.clone().with_span(Some(Span::new(key, 6..6))),
+            base.clone().with_span(None),
+            base.clone().with_note("another note"),
+            base.clone().with_notes(["expected `)`", "another note"]),
+            base.clone().with_lint(Some("unmatched")),
+            base.clone().with_code(Some("E0001")),
+        ];
+        for variant in variants {
+            assert_ne!(base, variant);
+            assert_ne!(hash(&base), hash(&variant));
         }
     }
 }
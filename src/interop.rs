@@ -0,0 +1,18 @@
+//! Integrations with other diagnostic and instrumentation crates and tools.
+
+#[cfg(feature = "codespan")]
+mod codespan;
+#[cfg(all(feature = "gcc", not(feature = "smol")))]
+mod gcc;
+#[cfg(all(feature = "log", not(feature = "smol")))]
+pub(crate) mod log;
+#[cfg(all(feature = "log", not(feature = "smol")))]
+pub use self::log::DiagnosticLogger;
+#[cfg(feature = "miette")]
+mod miette;
+#[cfg(feature = "serde")]
+pub(crate) mod serde;
+#[cfg(all(feature = "tracing", not(feature = "smol")))]
+mod tracing;
+#[cfg(all(feature = "tracing", not(feature = "smol")))]
+pub use self::tracing::DiagnosticLayer;
@@ -0,0 +1,273 @@
+//! Conversions between nurse and [`codespan_reporting`] diagnostics.
+//!
+//! Files are identified by their [`LookupKey`],
+//! so converted diagnostics can be rendered by either crate.
+//! [`TerminalReporter`] implements [`Files`] to provide the registered files to `codespan_reporting`.
+
+use codespan_reporting::diagnostic::{
+    Diagnostic as CodespanDiagnostic, Label, LabelStyle, Severity,
+};
+
+use crate::{
+    diagnostic::{Diagnostic, Level, Note},
+    reporter::LookupKey,
+    span::Span,
+};
+
+#[cfg(not(feature = "smol"))]
+use std::ops::Range;
+
+#[cfg(not(feature = "smol"))]
+use anstream::stream::{AsLockedWrite, RawStream};
+#[cfg(not(feature = "smol"))]
+use codespan_reporting::files::{Error, Files};
+
+#[cfg(not(feature = "smol"))]
+use crate::reporter::TerminalReporter;
+
+/// The prefix given to help messages, which `codespan_reporting` stores as ordinary notes.
+const HELP: &str = "help: ";
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Level {
+        match severity {
+            Severity::Bug | Severity::Error => Level::Error,
+            Severity::Warning => Level::Warn,
+            Severity::Note | Severity::Help => Level::Info,
+        }
+    }
+}
+
+impl From<Level> for Severity {
+    fn from(level: Level) -> Severity {
+        match level {
+            Level::Error => Severity::Error,
+            Level::Warn => Severity::Warning,
+            Level::Info | Level::Debug => Severity::Note,
+        }
+    }
+}
+
+/// Converts a `codespan_reporting` diagnostic labelling files registered with a [`TerminalReporter`](crate::TerminalReporter).
+///
+/// The first primary label becomes the span of the diagnostic,
+/// while every other label becomes a spanned note.
+/// Notes starting with `help: ` become help messages.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use codespan_reporting::diagnostic::{Diagnostic as CodespanDiagnostic, Label};
+///
+/// let mut reporter = TerminalReporter::default();
+/// let file = reporter.register_file("main.rs", "let x: u8 = 256;");
+///
+/// let diagnostic: Diagnostic = CodespanDiagnostic::error()
+///     .with_code("E0080")
+///     .with_message("literal out of range for `u8`")
+///     .with_labels(vec![Label::primary(file, 12..15)])
+///     .with_notes(vec!["help: consider using `u16` instead".to_owned()])
+///     .into();
+///
+/// assert_eq!(diagnostic.code(), Some("E0080"));
+/// assert_eq!(diagnostic.span(), Some(Span::new(file, 12..15)));
+/// assert!(diagnostic.notes()[0].is_help());
+/// ```
+impl From<CodespanDiagnostic<LookupKey>> for Diagnostic {
+    fn from(diagnostic: CodespanDiagnostic<LookupKey>) -> Diagnostic {
+        let span = |label: &Label<LookupKey>| Span {
+            lookup: label.file_id,
+            start: label.range.start,
+            end: label.range.end,
+        };
+
+        let primary = diagnostic
+            .labels
+            .iter()
+            .position(|label| label.style == LabelStyle::Primary);
+
+        let mut converted = Diagnostic::error(diagnostic.message)
+            .with_span(primary.map(|i| span(&diagnostic.labels[i])))
+            .with_code(diagnostic.code);
+        converted.level = diagnostic.severity.into();
+
+        for (i, label) in diagnostic.labels.iter().enumerate() {
+            // A diagnostic's span has no message of its own, so a primary label's message is kept as a note
+            if Some(i) == primary && label.message.is_empty() {
+                continue;
+            }
+
+            converted.add_spanned_note(label.message.clone(), span(label));
+        }
+
+        for note in diagnostic.notes {
+            match note.strip_prefix(HELP) {
+                Some(help) => converted.add_help(help),
+                None => converted.add_note(note),
+            };
+        }
+
+        converted
+    }
+}
+
+/// Converts a diagnostic into a `codespan_reporting` diagnostic.
+///
+/// The span of the diagnostic becomes its primary label,
+/// and spanned notes become secondary labels.
+/// Help messages are prefixed with `help: `.
+impl From<Diagnostic> for CodespanDiagnostic<LookupKey> {
+    fn from(diagnostic: Diagnostic) -> CodespanDiagnostic<LookupKey> {
+        let mut labels: Vec<Label<LookupKey>> = diagnostic
+            .span
+            .map(|span| Label::primary(span.lookup, span.range()))
+            .into_iter()
+            .collect();
+        let mut notes = Vec::new();
+
+        for Note { value, span, help } in diagnostic.notes {
+            match span {
+                Some(span) => {
+                    labels.push(Label::secondary(span.lookup, span.range()).with_message(value))
+                }
+                None if help => notes.push(format!("{HELP}{value}")),
+                None => notes.push(value),
+            }
+        }
+
+        CodespanDiagnostic {
+            severity: diagnostic.level.into(),
+            code: diagnostic.code,
+            message: diagnostic.message,
+            labels,
+            notes,
+        }
+    }
+}
+
+/// Provides the files registered with the reporter,
+/// allowing `codespan_reporting` to render diagnostics using their [`LookupKey`]s.
+#[cfg(not(feature = "smol"))]
+impl<'a, T: RawStream + AsLockedWrite + Send> Files<'a> for TerminalReporter<T> {
+    type FileId = LookupKey;
+    type Name = &'a str;
+    type Source = &'a str;
+
+    fn name(&'a self, id: LookupKey) -> Result<&'a str, Error> {
+        self.file(id)
+            .map(|(name, _)| name)
+            .ok_or(Error::FileMissing)
+    }
+
+    fn source(&'a self, id: LookupKey) -> Result<&'a str, Error> {
+        self.file(id)
+            .map(|(_, lookup)| lookup.source())
+            .ok_or(Error::FileMissing)
+    }
+
+    fn line_index(&'a self, id: LookupKey, byte_index: usize) -> Result<usize, Error> {
+        let (_, lookup) = self.file(id).ok_or(Error::FileMissing)?;
+        Ok(lookup.line_n(byte_index))
+    }
+
+    fn line_range(&'a self, id: LookupKey, line_index: usize) -> Result<Range<usize>, Error> {
+        let (_, lookup) = self.file(id).ok_or(Error::FileMissing)?;
+        let lines = lookup.line_count();
+
+        let line_start = |line: usize| match line.cmp(&lines) {
+            std::cmp::Ordering::Less => Ok(lookup.line_start(line)),
+            std::cmp::Ordering::Equal => Ok(lookup.file_len()),
+            std::cmp::Ordering::Greater => Err(Error::LineTooLarge {
+                given: line,
+                max: lines - 1,
+            }),
+        };
+
+        Ok(line_start(line_index)?..line_start(line_index + 1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let file = LookupKey::default();
+
+        let mut diagnostic = Diagnostic::spanned_warning(Span::new(file, 12..15), "out of range")
+            .with_code(Some("E0080"));
+        diagnostic.add_spanned_note("declared here", Span::new(file, 4..5));
+        diagnostic.add_note("the type is `u8`");
+        diagnostic.add_help("consider using `u16` instead");
+
+        let converted = CodespanDiagnostic::from(diagnostic.clone());
+        assert_eq!(converted.severity, Severity::Warning);
+        assert_eq!(
+            converted.labels,
+            [
+                Label::primary(file, 12..15),
+                Label::secondary(file, 4..5).with_message("declared here"),
+            ]
+        );
+        assert_eq!(
+            converted.notes,
+            ["the type is `u8`", "help: consider using `u16` instead"]
+        );
+
+        assert_eq!(Diagnostic::from(converted), diagnostic);
+    }
+
+    #[test]
+    fn primary_label_message() {
+        let file = LookupKey::default();
+        let diagnostic: Diagnostic = CodespanDiagnostic::bug()
+            .with_message("internal compiler error")
+            .with_labels(vec![
+                Label::secondary(file, 0..3),
+                Label::primary(file, 4..5).with_message("while checking this"),
+            ])
+            .into();
+
+        assert_eq!(diagnostic.level(), Level::Error);
+        assert_eq!(diagnostic.span(), Some(Span::new(file, 4..5)));
+        let notes: Vec<_> = diagnostic
+            .notes()
+            .iter()
+            .map(|note| (note.value(), note.span()))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                ("", Some(Span::new(file, 0..3))),
+                ("while checking this", Some(Span::new(file, 4..5))),
+            ]
+        );
+    }
+
+    #[cfg(not(feature = "smol"))]
+    #[test]
+    fn files() {
+        let mut reporter = TerminalReporter::default();
+        let file = reporter.register_file("main.rs", "fn main() {\n\tlet x = 1;\n}");
+
+        assert_eq!(reporter.name(file).ok(), Some("main.rs"));
+        assert_eq!(
+            reporter.source(file).ok(),
+            Some("fn main() {\n let x = 1;\n}")
+        );
+        assert_eq!(reporter.line_index(file, 14).ok(), Some(1));
+        assert_eq!(reporter.line_range(file, 1).ok(), Some(12..24));
+        assert_eq!(reporter.line_range(file, 2).ok(), Some(24..25));
+        assert!(matches!(
+            reporter.line_range(file, 3),
+            Err(Error::LineTooLarge { given: 4, max: 2 })
+        ));
+
+        assert!(matches!(
+            reporter.name(LookupKey::default()),
+            Err(Error::FileMissing)
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+//! Parsing of the plain-text diagnostics printed by GCC, Clang, and GNU `as`.
+//!
+//! Each diagnostic begins with a header line of the form `file:line:column: level: message`,
+//! where the column is omitted by assemblers.
+//! The caret line that follows a header determines the extent of its span,
+//! and `note:` headers are attached to the diagnostic before them.
+//! Any other lines, such as `In function 'main':` or `1 error generated.`, are skipped.
+
+use anstream::stream::{AsLockedWrite, RawStream};
+
+use crate::{
+    diagnostic::{Diagnostic, Level},
+    reporter::{LookupKey, TerminalReporter},
+    span::Span,
+};
+
+/// The levels that can appear in a header, with `None` marking notes.
+const LEVELS: [(&str, Option<Level>); 5] = [
+    ("fatal error", Some(Level::Error)),
+    ("error", Some(Level::Error)),
+    ("warning", Some(Level::Warn)),
+    ("remark", Some(Level::Info)),
+    ("note", None),
+];
+
+/// The first line of a diagnostic.
+#[derive(Debug, PartialEq, Eq)]
+struct Header<'a> {
+    path: &'a str,
+    /// The one-indexed line, absent for diagnostics not tied to a file
+    line: Option<usize>,
+    /// The one-indexed column, absent for diagnostics covering a whole line
+    column: Option<usize>,
+    /// The level of the diagnostic, or `None` for notes
+    level: Option<Level>,
+    message: &'a str,
+    /// The option controlling the diagnostic, e.g. `-Wunused-variable`
+    code: Option<&'a str>,
+}
+
+fn number(text: &str) -> Option<usize> {
+    text.bytes()
+        .all(|b| b.is_ascii_digit())
+        .then(|| text.parse().ok())
+        .flatten()
+}
+
+impl Header<'_> {
+    fn parse(line: &str) -> Option<Header<'_>> {
+        // Source lines echoed by GCC are indented, and can't be headers
+        if line.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        line.match_indices(": ").find_map(|(i, _)| {
+            let rest = &line[i + 2..];
+            let (level, message) = LEVELS.iter().find_map(|&(name, level)| {
+                let prefix = rest.get(..name.len())?;
+                let message = rest[name.len()..].strip_prefix(':')?;
+
+                prefix
+                    .eq_ignore_ascii_case(name)
+                    .then_some((level, message.trim_start()))
+            })?;
+
+            let location = &line[..i];
+            let (path, line, column) = match location.rsplit_once(':') {
+                Some((rest, last)) if number(last).is_some() => match rest.rsplit_once(':') {
+                    Some((path, line)) if number(line).is_some() => {
+                        (path, number(line), number(last))
+                    }
+                    _ => (rest, number(last), None),
+                },
+                // Diagnostics from the driver or linker are prefixed with the name of the tool
+                _ if !location.contains(char::is_whitespace) => (location, None, None),
+                _ => return None,
+            };
+
+            let (message, code) = match message
+                .strip_suffix(']')
+                .and_then(|message| message.rsplit_once(" ["))
+            {
+                Some((message, code)) if code.starts_with('-') => (message, Some(code)),
+                _ => (message, None),
+            };
+
+            Some(Header {
+                path,
+                line,
+                column,
+                level,
+                message,
+                code,
+            })
+        })
+    }
+}
+
+/// Finds the range underlined by a caret line, such as `  |     ~~^~~`,
+/// returning the number of characters before the caret and the width of the range, in characters.
+fn caret(line: &str) -> Option<(usize, usize)> {
+    // GCC prefixes the caret line with an empty line number gutter
+    let line = match line.split_once('|') {
+        Some((gutter, rest)) if gutter.trim().is_empty() => rest,
+        _ => line,
+    };
+    if !line.contains('^') || !line.chars().all(|c| matches!(c, ' ' | '~' | '^')) {
+        return None;
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    let caret = chars.iter().position(|&c| c == '^')?;
+    let start = chars[..caret]
+        .iter()
+        .rposition(|&c| c != '~')
+        .map_or(0, |i| i + 1);
+    let end = chars[caret + 1..]
+        .iter()
+        .position(|&c| c != '~')
+        .map_or(chars.len(), |i| caret + 1 + i);
+
+    Some((caret - start, end - start))
+}
+
+impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
+    /// Parses the plain-text diagnostics printed by GCC, Clang, or GNU `as` into [`Diagnostic`]s,
+    /// so they can be reported and rendered alongside the reporter's own diagnostics.
+    ///
+    /// Files are resolved with [`register_path`](TerminalReporter::register_path),
+    /// so files registered beforehand under the same path are used instead of being read from disk.
+    /// Diagnostics referring to files that can't be read are left without spans.
+    ///
+    /// Spans start at the column of the header and cover the range underlined by the caret line that follows it,
+    /// or the whole line when there is no column.
+    /// `note:` diagnostics become (spanned) notes of the diagnostic before them,
+    /// and options given in brackets after the message, such as `[-Wunused-variable]`, become codes.
+    ///
+    /// Columns are treated as byte offsets,
+    /// so GCC should be run with `-fdiagnostics-column-unit=byte` for sources containing tabs or non-ASCII characters.
+    /// The underline is measured in characters of the source line around the column.
+    ///
+    /// This method is not available with the `smol` feature.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// let mut reporter = TerminalReporter::default();
+    /// reporter.register_file("main.c", "int main(void) {\n    return x;\n}\n");
+    ///
+    /// let output = "\
+    /// main.c: In function 'main':
+    /// main.c:2:12: error: 'x' undeclared (first use in this function)
+    ///     2 |     return x;
+    ///       |            ^
+    /// main.c:2:12: note: each undeclared identifier is reported only once for each function it appears in
+    /// ";
+    ///
+    /// let diagnostics = reporter.parse_gcc(output);
+    /// assert_eq!(diagnostics.len(), 1);
+    /// assert_eq!(diagnostics[0].notes().len(), 1);
+    ///
+    /// reporter.report_all(diagnostics);
+    /// assert!(reporter.has_errors());
+    /// ```
+    pub fn parse_gcc(&mut self, output: &str) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let mut lines = output.lines().peekable();
+
+        while let Some(line) = lines.next() {
+            let Some(header) = Header::parse(line) else {
+                continue;
+            };
+
+            let mut underline = None;
+            while let Some(line) = lines.next_if(|line| Header::parse(line).is_none()) {
+                underline = underline.or_else(|| caret(line));
+            }
+
+            let span = header.line.and_then(|line| {
+                let key = self.register_path(header.path).ok()?;
+                self.line_span(key, line, header.column, underline)
+            });
+
+            match (header.level, diagnostics.last_mut()) {
+                (None, Some(parent)) => {
+                    match span {
+                        Some(span) => parent.add_spanned_note(header.message, span),
+                        None => parent.add_note(header.message),
+                    };
+                }
+                (level, _) => {
+                    let mut diagnostic = Diagnostic::error(header.message)
+                        .with_span(span)
+                        .with_code(header.code);
+                    diagnostic.level = level.unwrap_or(Level::Info);
+                    diagnostics.push(diagnostic);
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Creates a span from a one-indexed line and column,
+    /// covering the given underline or the whole line when there is no column.
+    fn line_span(
+        &self,
+        key: LookupKey,
+        line: usize,
+        column: Option<usize>,
+        underline: Option<(usize, usize)>,
+    ) -> Option<Span> {
+        let (_, lookup) = self.file(key)?;
+        let line = line
+            .checked_sub(1)
+            .filter(|&line| line < lookup.line_count())?;
+        let head = lookup.line_start(line);
+        let text = lookup.line(line).trim_end_matches(['\n', '\r']);
+
+        let Some(column) = column.and_then(|column| column.checked_sub(1)) else {
+            let indent = text.len() - text.trim_start().len();
+            return Some(Span::new(key, head + indent..head + text.len()));
+        };
+
+        // The caret line counts characters, while the column counts bytes
+        let chars: Vec<usize> = text
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([text.len()])
+            .collect();
+        let caret = chars.partition_point(|&i| i <= column) - 1;
+
+        let (before, width) = underline.unwrap_or((0, 1));
+        let start = caret.saturating_sub(before);
+        let end = (start + width).min(chars.len() - 1);
+        Some(Span::new(key, head + chars[start]..head + chars[end]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_headers() {
+        assert_eq!(
+            Header::parse("src/main.c:3:9: warning: unused variable 'y' [-Wunused-variable]"),
+            Some(Header {
+                path: "src/main.c",
+                line: Some(3),
+                column: Some(9),
+                level: Some(Level::Warn),
+                message: "unused variable 'y'",
+                code: Some("-Wunused-variable"),
+            })
+        );
+        assert_eq!(
+            Header::parse("boot.s:4: Error: no such instruction: `movz %eax'"),
+            Some(Header {
+                path: "boot.s",
+                line: Some(4),
+                column: None,
+                level: Some(Level::Error),
+                message: "no such instruction: `movz %eax'",
+                code: None,
+            })
+        );
+        assert_eq!(
+            Header::parse("gcc: fatal error: no input files"),
+            Some(Header {
+                path: "gcc",
+                line: None,
+                column: None,
+                level: Some(Level::Error),
+                message: "no input files",
+                code: None,
+            })
+        );
+
+        assert_eq!(Header::parse("main.c: In function 'main':"), None);
+        assert_eq!(Header::parse("    2 |     puts(\"error: oops\");"), None);
+        assert_eq!(Header::parse("puts(\"error: oops\");"), None);
+        assert_eq!(Header::parse("1 error generated."), None);
+    }
+
+    #[test]
+    fn parse_output() {
+        let mut reporter = TerminalReporter::default();
+        let key = reporter.register_file(
+            "main.c",
+            "int main(void) {\n    int y = 1 + \"a\";\n    return x;\n}\n",
+        );
+        let asm = reporter.register_file("boot.s", "start:\n    movz %eax\n");
+
+        let output = "\
+main.c:2:15: warning: initialization of 'int' from 'char *' [-Wint-conversion]
+    int y = 1 + \"a\";
+            ~~^~~~~
+main.c:3:12: error: use of undeclared identifier 'x'
+    return x;
+           ^
+main.c:2:9: note: 'y' declared here
+missing.c:1:1: note: included from here
+2 diagnostics generated.
+boot.s: Assembler messages:
+boot.s:2: Error: no such instruction: `movz %eax'
+";
+
+        let diagnostics = reporter.parse_gcc(output);
+        assert_eq!(diagnostics.len(), 3);
+
+        assert_eq!(diagnostics[0].level(), Level::Warn);
+        assert_eq!(diagnostics[0].code(), Some("-Wint-conversion"));
+        assert_eq!(diagnostics[0].span(), Some(Span::new(key, 29..36)));
+
+        let notes = diagnostics[1].notes();
+        assert_eq!(diagnostics[1].span(), Some(Span::new(key, 49..50)));
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].span(), Some(Span::new(key, 25..26)));
+        assert_eq!(notes[1].span(), None);
+
+        assert_eq!(diagnostics[2].span(), Some(Span::new(asm, 11..20)));
+    }
+
+    #[test]
+    fn non_ascii_columns() {
+        let mut reporter = TerminalReporter::default();
+        let key = reporter.register_file("main.c", "int main(void) {\n    int y = \"é\" + 1;\n}\n");
+
+        // Columns are bytes, while the caret line is aligned by characters
+        let output = "\
+main.c:2:18: warning: initialization of 'int' from 'char *' [-Wint-conversion]
+    2 |     int y = \"é\" + 1;
+      |             ~~~~^~~
+main.c:2:16: note: the string ends here
+main.c:2:15: note: inside of a character
+";
+
+        let diagnostics = reporter.parse_gcc(output);
+        assert_eq!(diagnostics[0].span(), Some(Span::new(key, 29..37)));
+
+        let notes = diagnostics[0].notes();
+        assert_eq!(notes[0].span(), Some(Span::new(key, 32..33)));
+        // A column inside of a character points to the character
+        assert_eq!(notes[1].span(), Some(Span::new(key, 30..32)));
+    }
+}
@@ -0,0 +1,295 @@
+//! A [`log`](::log) backend that prints log records as diagnostics.
+
+use ::log::{kv::Key, Log, Metadata, Record, SetLoggerError};
+use anstream::stream::{AsLockedWrite, RawStream};
+use slotmap::{Key as _, KeyData};
+
+use crate::{
+    diagnostic::{Diagnostic, Level, LevelFilter},
+    reporter::{LookupKey, SharedReporter},
+};
+
+impl From<::log::Level> for Level {
+    fn from(level: ::log::Level) -> Level {
+        match level {
+            ::log::Level::Error => Level::Error,
+            ::log::Level::Warn => Level::Warn,
+            ::log::Level::Info => Level::Info,
+            ::log::Level::Debug | ::log::Level::Trace => Level::Debug,
+        }
+    }
+}
+
+impl From<::log::LevelFilter> for LevelFilter {
+    fn from(filter: ::log::LevelFilter) -> LevelFilter {
+        match filter {
+            ::log::LevelFilter::Off => LevelFilter::Off,
+            ::log::LevelFilter::Error => LevelFilter::Error,
+            ::log::LevelFilter::Warn => LevelFilter::Warn,
+            ::log::LevelFilter::Info => LevelFilter::Info,
+            ::log::LevelFilter::Debug | ::log::LevelFilter::Trace => LevelFilter::Debug,
+        }
+    }
+}
+
+impl From<LevelFilter> for ::log::LevelFilter {
+    fn from(filter: LevelFilter) -> ::log::LevelFilter {
+        match filter {
+            LevelFilter::Off => ::log::LevelFilter::Off,
+            LevelFilter::Error => ::log::LevelFilter::Error,
+            LevelFilter::Warn => ::log::LevelFilter::Warn,
+            LevelFilter::Info => ::log::LevelFilter::Info,
+            LevelFilter::Debug => ::log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Converts a lookup key into the value attached to records by [`log_spanned!`](crate::log_spanned).
+#[doc(hidden)]
+#[inline]
+pub fn lookup_id(lookup: LookupKey) -> u64 {
+    lookup.data().as_ffi()
+}
+
+/// A [`Log`] implementation that prints records through a [`SharedReporter`].
+///
+/// Records are emitted as soon as they are logged,
+/// in the same format as any other diagnostic,
+/// with [`Trace`](::log::Level::Trace) records becoming [`Debug`](Level::Debug) diagnostics.
+/// Records logged with [`log_spanned!`](crate::log_spanned) carry a span into the diagnostic.
+///
+/// Spans should refer to files registered with the logger's reporter.
+/// A record only carries the raw key of its span's file,
+/// and the first files registered with different reporters share the same keys,
+/// so a span from another reporter refers to whichever file of this reporter has its key.
+/// Spans referring to files that aren't registered, or that don't lie within their file, are dropped.
+///
+/// Records logged while the reporter is locked, whether by another thread
+/// or by the logging thread itself, e.g. from a `Display` implementation called while printing,
+/// are held back until the reporter is next [locked](SharedReporter::lock) rather than waiting for it.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::{log_spanned, DiagnosticLogger, SharedReporter};
+///
+/// let reporter = SharedReporter::new(TerminalReporter::default());
+/// let file = reporter.register_file("main.rs", "let x = ;");
+///
+/// DiagnosticLogger::new(reporter.clone())
+///     .with_filter(log::LevelFilter::Info.into())
+///     .init()
+///     .unwrap();
+///
+/// log::info!("checking `main.rs`");
+/// log::debug!("hidden by the filter");
+/// log_spanned!(log::Level::Error, Span::new(file, 8..9), "expected expression");
+///
+/// assert_eq!(reporter.lock().error_count(), 1);
+/// ```
+#[derive(Debug)]
+pub struct DiagnosticLogger<T: RawStream + AsLockedWrite> {
+    reporter: SharedReporter<T>,
+    filter: LevelFilter,
+}
+
+impl<T: RawStream + AsLockedWrite + Send + 'static> DiagnosticLogger<T> {
+    /// Creates a logger printing records through the given `reporter`,
+    /// with a filter that allows every record.
+    #[inline]
+    pub fn new(reporter: SharedReporter<T>) -> DiagnosticLogger<T> {
+        DiagnosticLogger {
+            reporter,
+            filter: LevelFilter::Debug,
+        }
+    }
+
+    /// Gets the reporter that records are printed through.
+    #[inline]
+    pub fn reporter(&self) -> &SharedReporter<T> {
+        &self.reporter
+    }
+
+    /// Gets the filter level of the logger.
+    #[inline]
+    pub fn filter(&self) -> LevelFilter {
+        self.filter
+    }
+
+    /// Sets the filter level of the logger to the given filter level.
+    #[inline]
+    pub fn set_filter(&mut self, filter: LevelFilter) {
+        self.filter = filter;
+    }
+
+    /// Returns the logger with the filter level set to the given filter level.
+    #[inline]
+    pub fn with_filter(mut self, filter: LevelFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Installs the logger as the global logger,
+    /// setting the maximum level of the `log` crate to the logger's filter level.
+    ///
+    /// ## Errors
+    ///
+    /// This function will fail if a global logger has already been installed.
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let filter = self.filter.into();
+        ::log::set_boxed_logger(Box::new(self))?;
+        ::log::set_max_level(filter);
+
+        Ok(())
+    }
+}
+
+impl<T: RawStream + AsLockedWrite + Send + 'static> Log for DiagnosticLogger<T> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter.passes(metadata.level().into())
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(record.args().to_string());
+        diagnostic.level = record.level().into();
+
+        let value = |key: &str| record.key_values().get(Key::from_str(key))?.to_u64();
+        let offset = |key: &str| usize::try_from(value(key)?).ok();
+        let span = (
+            value("nurse_lookup"),
+            offset("nurse_start"),
+            offset("nurse_end"),
+        );
+
+        // Locking the reporter would deadlock if the record was logged while it's locked
+        self.reporter.lock_or_defer(move |reporter| {
+            if let (Some(lookup), Some(start), Some(end)) = span {
+                let lookup = LookupKey::from(KeyData::from_ffi(lookup));
+                // Keys and offsets from another reporter would lead to panics when printed
+                diagnostic.set_span(reporter.checked_span(lookup, start, end));
+            }
+
+            // There's nowhere to surface a failure to print a log record
+            let _ = reporter.emit(diagnostic);
+        });
+    }
+
+    fn flush(&self) {
+        // Prints any records held back while the reporter was locked
+        self.reporter.lock_or_defer(|_| {});
+    }
+}
+
+/// Logs a record through the [`log`](::log) crate,
+/// attaching the span of a [`Span`], [`Spanned`](crate::Spanned), or `Option` of either when available.
+///
+/// Spans are attached as key-values understood by [`DiagnosticLogger`],
+/// which uses them to print the record with a snippet of the source.
+/// Other loggers print them as ordinary key-values.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::log_spanned;
+///
+/// # let mut reporter = TerminalReporter::default();
+/// # let file = reporter.register_file("main.rs", "let x = 266u8;");
+/// let token = Spanned::new(266, Span::new(file, 8..13));
+/// log_spanned!(log::Level::Warn, token, "literal out of range for `u8`");
+///
+/// let span: Option<Span> = None;
+/// log_spanned!(log::Level::Info, span, "no span to attach");
+/// ```
+#[macro_export]
+macro_rules! log_spanned {
+    ($level:expr, $span:expr, $fmt:literal $($arg:tt)*) => {{
+        use $crate::MaybeSpanned;
+        match $span.get_span() {
+            Some(span) => $crate::__log::log!(
+                $level,
+                nurse_lookup = $crate::__lookup_id(span.lookup()),
+                nurse_start = span.start(),
+                nurse_end = span.end();
+                $fmt $($arg)*
+            ),
+            None => $crate::__log::log!($level, $fmt $($arg)*),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use ::log::kv::{Source, ToValue};
+
+    use super::*;
+    use crate::reporter::TerminalReporter;
+
+    fn log(logger: &DiagnosticLogger<Vec<u8>>, lookup: u64, start: u64, end: u64) {
+        let kvs = [
+            ("nurse_lookup", lookup.to_value()),
+            ("nurse_start", start.to_value()),
+            ("nurse_end", end.to_value()),
+        ];
+
+        logger.log(
+            &Record::builder()
+                .level(::log::Level::Error)
+                .args(format_args!("expected expression"))
+                .key_values(&kvs as &dyn Source)
+                .build(),
+        );
+    }
+
+    #[test]
+    fn spans_outside_the_file_are_dropped() {
+        let reporter = SharedReporter::new(TerminalReporter::new(Vec::new()));
+        let file = lookup_id(reporter.register_file("a.txt", "let x = ;\n"));
+        let logger = DiagnosticLogger::new(reporter.clone());
+
+        let cases = [
+            (file, 8, 9, true),
+            (file, 10, 10, true),
+            (file, 100, 105, false),
+            (file, 8, 4, false),
+            (file + 1, 0, 1, false),
+        ];
+
+        for (lookup, start, end, spanned) in cases {
+            let printed = reporter.lock().emitter.as_inner().len();
+            log(&logger, lookup, start, end);
+
+            let output = reporter.lock().emitter.as_inner()[printed..].to_vec();
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.starts_with("error: expected expression"), "{output}");
+            assert_eq!(
+                output.contains("a.txt"),
+                spanned,
+                "{start}..{end}: {output}"
+            );
+        }
+    }
+
+    #[test]
+    fn records_while_locked_are_held_back() {
+        let reporter = SharedReporter::new(TerminalReporter::new(Vec::new()));
+        let file = lookup_id(reporter.register_file("a.txt", "let x = ;\n"));
+        let logger = DiagnosticLogger::new(reporter.clone());
+
+        // Logging while holding the lock doesn't deadlock
+        let locked = reporter.lock();
+        log(&logger, file, 8, 9);
+        assert!(locked.emitter.as_inner().is_empty());
+        drop(locked);
+
+        logger.flush();
+        let output = String::from_utf8(reporter.lock().emitter.as_inner().clone()).unwrap();
+        assert!(output.starts_with("error: expected expression"), "{output}");
+        assert!(output.contains("a.txt"), "{output}");
+    }
+}
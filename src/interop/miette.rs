@@ -0,0 +1,359 @@
+//! Conversions between nurse and [`miette`](::miette) diagnostics.
+//!
+//! Since miette diagnostics carry their own source code,
+//! converting one registers its source with a [`TerminalReporter`],
+//! and converting a diagnostic into a [`Report`] attaches the source of the file it refers to.
+
+use ::miette::Severity;
+
+use crate::diagnostic::Level;
+
+#[cfg(not(feature = "smol"))]
+use ::miette::{LabeledSpan, MietteDiagnostic, NamedSource, Report, SourceSpan};
+#[cfg(not(feature = "smol"))]
+use anstream::stream::{AsLockedWrite, RawStream};
+
+#[cfg(not(feature = "smol"))]
+use crate::{diagnostic::Diagnostic, reporter::TerminalReporter, span::Span};
+
+/// The name given to sources that miette doesn't provide a name for.
+#[cfg(not(feature = "smol"))]
+const UNNAMED: &str = "<unknown>";
+
+impl From<Severity> for Level {
+    fn from(severity: Severity) -> Level {
+        match severity {
+            Severity::Error => Level::Error,
+            Severity::Warning => Level::Warn,
+            Severity::Advice => Level::Info,
+        }
+    }
+}
+
+impl From<Level> for Severity {
+    fn from(level: Level) -> Severity {
+        match level {
+            Level::Error => Severity::Error,
+            Level::Warn => Severity::Warning,
+            Level::Info | Level::Debug => Severity::Advice,
+        }
+    }
+}
+
+#[cfg(not(feature = "smol"))]
+impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
+    /// Converts a miette diagnostic, registering its source code with the reporter.
+    ///
+    /// The first primary label, or the first label if none are primary,
+    /// becomes the span of the diagnostic,
+    /// while the text of every label becomes a spanned note.
+    /// The help text, URL, related diagnostics and causes become notes.
+    /// Labels that don't lie on character boundaries within the source are dropped,
+    /// as are all labels of sources that aren't valid UTF-8.
+    ///
+    /// A source is only registered once,
+    /// so converting several diagnostics with the same source shares a file.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// use miette::{LabeledSpan, MietteDiagnostic, NamedSource, Report};
+    ///
+    /// let report = Report::new(
+    ///     MietteDiagnostic::new("literal out of range for `u8`")
+    ///         .with_code("E0080")
+    ///         .with_label(LabeledSpan::at(12..15, "this literal"))
+    ///         .with_help("consider using `u16` instead"),
+    /// )
+    /// .with_source_code(NamedSource::new("main.rs", "let x: u8 = 256;"));
+    ///
+    /// let mut reporter = TerminalReporter::default();
+    /// let diagnostic = reporter.from_miette(report.as_ref());
+    ///
+    /// assert_eq!(diagnostic.code(), Some("E0080"));
+    /// assert_eq!(diagnostic.span().map(|span| span.range()), Some(12..15));
+    /// ```
+    pub fn from_miette(&mut self, diagnostic: &dyn ::miette::Diagnostic) -> Diagnostic {
+        let mut converted = Diagnostic::error(diagnostic.to_string())
+            .with_code(diagnostic.code().map(|code| code.to_string()));
+        converted.level = diagnostic.severity().unwrap_or_default().into();
+
+        let labels: Vec<LabeledSpan> = diagnostic.labels().into_iter().flatten().collect();
+        let source = diagnostic.source_code().and_then(|source| {
+            // Reading an empty span with unlimited context yields the entire source
+            source
+                .read_span(&SourceSpan::from(0..0), usize::MAX, usize::MAX)
+                .ok()
+        });
+
+        // Label offsets can't be mapped into a source that isn't valid UTF-8
+        let contents = source
+            .as_ref()
+            .filter(|_| !labels.is_empty())
+            .and_then(|contents| {
+                Some((contents.name(), std::str::from_utf8(contents.data()).ok()?))
+            });
+
+        if let Some((name, text)) = contents {
+            let key = self.find_or_register(name.unwrap_or(UNNAMED), text);
+
+            // miette doesn't check that labels lie within their source
+            let labels: Vec<(Span, &LabeledSpan)> = labels
+                .iter()
+                .filter_map(|label| {
+                    let end = label.offset().checked_add(label.len())?;
+                    Some((self.checked_span(key, label.offset(), end)?, label))
+                })
+                .collect();
+
+            let primary = labels
+                .iter()
+                .position(|(_, label)| label.primary())
+                .unwrap_or_default();
+            converted.set_span(labels.get(primary).map(|&(span, _)| span));
+
+            for (span, label) in &labels {
+                if let Some(text) = label.label() {
+                    converted.add_spanned_note(text, *span);
+                }
+            }
+        }
+
+        if let Some(help) = diagnostic.help() {
+            converted.add_help(help.to_string());
+        }
+        if let Some(url) = diagnostic.url() {
+            converted.add_note(format!("for more information, see {url}"));
+        }
+        for related in diagnostic.related().into_iter().flatten() {
+            let level = Level::from(related.severity().unwrap_or_default());
+            converted.add_note(format!("{}: {related}", level.title()));
+        }
+
+        let mut cause = diagnostic
+            .diagnostic_source()
+            .map(|source| source as &dyn std::error::Error)
+            .or_else(|| diagnostic.source());
+        while let Some(error) = cause {
+            converted.add_note(format!("caused by: {error}"));
+            cause = error.source();
+        }
+
+        converted
+    }
+
+    /// Converts a diagnostic into a miette [`Report`] carrying the source of the file it refers to.
+    ///
+    /// The span of the diagnostic becomes the primary label,
+    /// and notes spanning the same file become secondary labels.
+    /// Since miette has a single help message,
+    /// help and unspanned notes are combined into it.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// let mut reporter = TerminalReporter::default();
+    /// let file = reporter.register_file("main.rs", "let x: u8 = 256;");
+    ///
+    /// let diagnostic = error!(Span::new(file, 12..15), "literal out of range for `u8`")
+    ///     .with_help("consider using `u16` instead");
+    /// let report = reporter.to_miette(&diagnostic);
+    ///
+    /// assert_eq!(report.help().unwrap().to_string(), "consider using `u16` instead");
+    /// ```
+    pub fn to_miette(&self, diagnostic: &Diagnostic) -> Report {
+        let mut converted =
+            MietteDiagnostic::new(diagnostic.message()).with_severity(diagnostic.level().into());
+        if let Some(code) = diagnostic.code() {
+            converted = converted.with_code(code);
+        }
+
+        let file = diagnostic
+            .span()
+            .and_then(|span| self.file(span.lookup()).map(|file| (span, file)));
+
+        if let Some((span, _)) = file {
+            converted = converted.and_label(LabeledSpan::new_primary_with_span(None, span.range()));
+        }
+
+        let mut help = Vec::new();
+        for note in diagnostic.notes() {
+            match note.span() {
+                Some(note_span)
+                    if file.is_some_and(|(span, _)| span.lookup() == note_span.lookup()) =>
+                {
+                    converted = converted.and_label(LabeledSpan::new_with_span(
+                        Some(note.value().to_owned()),
+                        note_span.range(),
+                    ));
+                }
+                _ if note.is_help() => help.push(note.value().to_owned()),
+                _ => help.push(format!("note: {}", note.value())),
+            }
+        }
+
+        if !help.is_empty() {
+            converted = converted.with_help(help.join("\n"));
+        }
+
+        let report = Report::new(converted);
+        match file {
+            Some((_, (name, lookup))) => {
+                report.with_source_code(NamedSource::new(name, lookup.source().to_owned()))
+            }
+            None => report,
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "smol")))]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report::new(
+            MietteDiagnostic::new("literal out of range for `u8`")
+                .with_severity(Severity::Warning)
+                .with_code("E0080")
+                .with_url("https://example.com/E0080")
+                .with_labels([
+                    LabeledSpan::at(4..5, "declared here"),
+                    LabeledSpan::new_primary_with_span(Some("this literal".to_owned()), 12..15),
+                ])
+                .with_help("consider using `u16` instead"),
+        )
+        .with_source_code(NamedSource::new("main.rs", "let\tx: u8 = 256;"))
+    }
+
+    #[test]
+    fn from_miette() {
+        let mut reporter = TerminalReporter::default();
+        let diagnostic = reporter.from_miette(report().as_ref());
+        let key = diagnostic.span().unwrap().lookup();
+
+        assert_eq!(diagnostic.level(), Level::Warn);
+        assert_eq!(diagnostic.code(), Some("E0080"));
+        assert_eq!(diagnostic.span(), Some(Span::new(key, 12..15)));
+        assert_eq!(reporter.file(key).map(|(name, _)| name), Some("main.rs"));
+
+        let notes: Vec<_> = diagnostic
+            .notes()
+            .iter()
+            .map(|note| (note.value(), note.span(), note.is_help()))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                ("declared here", Some(Span::new(key, 4..5)), false),
+                ("this literal", Some(Span::new(key, 12..15)), false),
+                ("consider using `u16` instead", None, true),
+                (
+                    "for more information, see https://example.com/E0080",
+                    None,
+                    false
+                ),
+            ]
+        );
+
+        // The same source, containing a tab, is only registered once
+        let again = reporter.from_miette(report().as_ref());
+        assert_eq!(again.span().map(|span| span.lookup()), Some(key));
+    }
+
+    #[test]
+    fn from_miette_without_labels() {
+        let mut reporter = TerminalReporter::default();
+        let report = Report::new(MietteDiagnostic::new("no input files"))
+            .with_source_code(NamedSource::new("main.rs", ""));
+
+        let diagnostic = reporter.from_miette(report.as_ref());
+        assert_eq!(diagnostic.level(), Level::Error);
+        assert_eq!(diagnostic.span(), None);
+        assert!(reporter.is_empty());
+    }
+
+    #[test]
+    fn from_miette_with_invalid_labels() {
+        let mut reporter = TerminalReporter::default();
+        let report = Report::new(
+            MietteDiagnostic::new("unexpected character")
+                .with_label(LabeledSpan::new_primary_with_span(
+                    Some("past the end".to_owned()),
+                    10..20,
+                ))
+                .with_label(LabeledSpan::at(5..6, "inside of `é`"))
+                .with_label(LabeledSpan::at(0..3, "this keyword")),
+        )
+        .with_source_code(NamedSource::new("main.rs", "let é = 1;"));
+
+        let diagnostic = reporter.from_miette(report.as_ref());
+        let key = diagnostic.span().unwrap().lookup();
+        assert_eq!(diagnostic.span(), Some(Span::new(key, 0..3)));
+        assert_eq!(diagnostic.notes().len(), 1);
+
+        // Offsets into a source that isn't valid UTF-8 can't be trusted
+        let report = Report::new(
+            MietteDiagnostic::new("unexpected byte").with_label(LabeledSpan::at(1..2, "here")),
+        )
+        .with_source_code(NamedSource::new("data.bin", vec![0xff, b'a', b'b']));
+
+        let diagnostic = reporter.from_miette(report.as_ref());
+        assert_eq!(diagnostic.span(), None);
+        assert!(diagnostic.notes().is_empty());
+    }
+
+    #[test]
+    fn to_miette() {
+        let mut reporter = TerminalReporter::default();
+        let file = reporter.register_file("main.rs", "let x: u8 = 256;");
+        let other = reporter.register_file("lib.rs", "");
+
+        let mut diagnostic = Diagnostic::spanned_warning(Span::new(file, 12..15), "out of range")
+            .with_code(Some("E0080"));
+        diagnostic.add_spanned_note("declared here", Span::new(file, 4..5));
+        diagnostic.add_note("the type is `u8`");
+        diagnostic.add_help("consider using `u16` instead");
+        diagnostic.add_spanned_note("elsewhere", Span::new(other, 0..0));
+
+        let report = reporter.to_miette(&diagnostic);
+        assert_eq!(report.to_string(), "out of range");
+        assert_eq!(report.severity(), Some(Severity::Warning));
+        assert_eq!(report.code().unwrap().to_string(), "E0080");
+
+        let labels: Vec<_> = report
+            .labels()
+            .unwrap()
+            .map(|label| {
+                (
+                    label.label().map(str::to_owned),
+                    *label.inner(),
+                    label.primary(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (None, SourceSpan::from(12..15), true),
+                (
+                    Some("declared here".to_owned()),
+                    SourceSpan::from(4..5),
+                    false
+                ),
+            ]
+        );
+        assert_eq!(
+            report.help().unwrap().to_string(),
+            "note: the type is `u8`\nconsider using `u16` instead\nnote: elsewhere"
+        );
+
+        let source = report.source_code().unwrap();
+        let contents = source
+            .read_span(&SourceSpan::from(0..0), usize::MAX, usize::MAX)
+            .unwrap();
+        assert_eq!(contents.name(), Some("main.rs"));
+        assert_eq!(contents.data(), b"let x: u8 = 256;");
+    }
+}
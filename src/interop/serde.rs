@@ -0,0 +1,332 @@
+//! Serialization and deserialization of diagnostics with [`serde`](::serde).
+//!
+//! Lookup keys are only meaningful within the reporter that created them,
+//! so spans refer to their file by its registered name instead.
+//! Names are resolved through a table of the reporter's files,
+//! installed on the current thread by [`TerminalReporter::serde_scope`].
+
+use std::{
+    collections::HashSet,
+    sync::{Mutex, PoisonError},
+};
+
+use ::serde::{Deserialize, Deserializer};
+
+#[cfg(feature = "terminal")]
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, rc::Rc};
+
+#[cfg(feature = "terminal")]
+use ::serde::{de, ser, Serialize, Serializer};
+#[cfg(feature = "terminal")]
+use anstream::stream::{AsLockedWrite, RawStream};
+#[cfg(feature = "terminal")]
+use slotmap::SecondaryMap;
+
+use crate::{
+    diagnostic::{Diagnostic, Level, Note},
+    span::Span,
+};
+
+#[cfg(feature = "terminal")]
+use crate::reporter::{LookupKey, TerminalReporter};
+
+/// The deserialized form of a [`Diagnostic`].
+///
+/// Lint names are `'static`, which a derived implementation would borrow from the input,
+/// so they are deserialized as owned strings and interned instead.
+#[derive(Deserialize)]
+struct DiagnosticRepr {
+    level: Level,
+    message: String,
+    #[serde(default)]
+    notes: Vec<Note>,
+    #[serde(default)]
+    span: Option<Span>,
+    #[serde(default)]
+    lint: Option<String>,
+    #[serde(default)]
+    code: Option<String>,
+}
+
+impl<'de> Deserialize<'de> for Diagnostic {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Diagnostic, D::Error> {
+        let repr = DiagnosticRepr::deserialize(deserializer)?;
+
+        Ok(Diagnostic {
+            level: repr.level,
+            message: repr.message,
+            notes: repr.notes,
+            span: repr.span,
+            lint: repr.lint.map(intern),
+            code: repr.code,
+            source: None,
+        })
+    }
+}
+
+/// Gets a `'static` copy of a lint name,
+/// leaking each distinct name once and reusing it afterwards.
+pub(crate) fn intern(name: String) -> &'static str {
+    static NAMES: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    let names = names.get_or_insert_with(HashSet::new);
+    match names.get(name.as_str()) {
+        Some(&interned) => interned,
+        None => {
+            let leaked: &'static str = Box::leak(name.into_boxed_str());
+            names.insert(leaked);
+            leaked
+        }
+    }
+}
+
+/// The registered names and lengths of a reporter's files.
+#[cfg(feature = "terminal")]
+#[derive(Debug, Default)]
+struct Files {
+    names: SecondaryMap<LookupKey, String>,
+    lens: SecondaryMap<LookupKey, usize>,
+    /// The first file registered with each name
+    keys: HashMap<String, LookupKey>,
+}
+
+#[cfg(feature = "terminal")]
+impl Files {
+    fn new<'a, I: Iterator<Item = (LookupKey, &'a str, usize)>>(files: I) -> Files {
+        let mut table = Files::default();
+        for (key, name, len) in files {
+            table.names.insert(key, name.to_owned());
+            table.lens.insert(key, len);
+            table.keys.entry(name.to_owned()).or_insert(key);
+        }
+
+        table
+    }
+
+    /// Runs `f` with this table installed on the current thread,
+    /// restoring the previous table afterwards, even if `f` panics.
+    fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<Rc<Files>>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                FILES.with(|files| *files.borrow_mut() = self.0.take());
+            }
+        }
+
+        let _restore = Restore(FILES.with(|files| files.replace(Some(Rc::new(self)))));
+        f()
+    }
+
+    fn current() -> Option<Rc<Files>> {
+        FILES.with(|files| files.borrow().clone())
+    }
+}
+
+#[cfg(feature = "terminal")]
+thread_local! {
+    static FILES: RefCell<Option<Rc<Files>>> = const { RefCell::new(None) };
+}
+
+/// The serialized form of a [`Span`].
+#[cfg(feature = "terminal")]
+#[derive(Serialize, Deserialize)]
+struct SpanRepr<'a> {
+    #[serde(borrow)]
+    file: Cow<'a, str>,
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature = "terminal")]
+impl Serialize for Span {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let files = Files::current().ok_or_else(|| {
+            ser::Error::custom(
+                "spans can only be serialized within `TerminalReporter::serde_scope`",
+            )
+        })?;
+        let file = files
+            .names
+            .get(self.lookup)
+            .ok_or_else(|| ser::Error::custom("span refers to a file that isn't registered"))?;
+
+        SpanRepr {
+            file: Cow::Borrowed(file),
+            start: self.start,
+            end: self.end,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "terminal")]
+impl<'de> Deserialize<'de> for Span {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Span, D::Error> {
+        let repr = SpanRepr::deserialize(deserializer)?;
+        let files = Files::current().ok_or_else(|| {
+            de::Error::custom(
+                "spans can only be deserialized within `TerminalReporter::serde_scope`",
+            )
+        })?;
+        let lookup = *files.keys.get(repr.file.as_ref()).ok_or_else(|| {
+            de::Error::custom(format_args!("no file named `{}` is registered", repr.file))
+        })?;
+
+        if repr.end < repr.start {
+            return Err(de::Error::custom(format_args!(
+                "span {}..{} ends before it starts",
+                repr.start, repr.end
+            )));
+        }
+        let len = files.lens[lookup];
+        if repr.end > len {
+            return Err(de::Error::custom(format_args!(
+                "span {}..{} lies outside of `{}`, which is {len} bytes long",
+                repr.start, repr.end, repr.file
+            )));
+        }
+
+        Ok(Span {
+            lookup,
+            start: repr.start,
+            end: repr.end,
+        })
+    }
+}
+
+#[cfg(all(feature = "terminal", not(feature = "smol")))]
+impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
+    /// Runs `f` with the reporter's files available for serializing and deserializing spans.
+    ///
+    /// Spans are serialized with the name of their file,
+    /// and deserialized spans refer to the first registered file with that name,
+    /// so files should be registered before diagnostics referring to them are deserialized.
+    /// Deserializing a span fails if it ends before it starts or past the end of its file.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// let mut reporter = TerminalReporter::default();
+    /// let file = reporter.register_file("main.rs", "let x = ;");
+    /// let diagnostic = error!(Span::new(file, 8..9), "expected expression");
+    ///
+    /// let json = reporter.serde_scope(|| serde_json::to_string(&diagnostic)).unwrap();
+    /// assert!(json.contains(r#""file":"main.rs""#));
+    ///
+    /// let mut other = TerminalReporter::default();
+    /// other.register_file("main.rs", "let x = ;");
+    ///
+    /// let deserialized: Diagnostic = other.serde_scope(|| serde_json::from_str(&json)).unwrap();
+    /// assert_eq!(deserialized.message(), "expected expression");
+    /// ```
+    pub fn serde_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        Files::new(
+            self.lookups
+                .iter()
+                .map(|(key, (name, lookup))| (key, name.as_str(), lookup.file_len())),
+        )
+        .scope(f)
+    }
+}
+
+#[cfg(all(feature = "terminal", feature = "smol"))]
+impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
+    /// Runs `f` with the reporter's files available for serializing and deserializing spans.
+    ///
+    /// Spans are serialized with the name of their file,
+    /// and deserialized spans refer to the first registered file with that name,
+    /// so files should be registered before diagnostics referring to them are deserialized.
+    /// Deserializing a span fails if it ends before it starts or past the end of its file.
+    pub async fn serde_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let files = Files::new(
+            self.lookups
+                .lock()
+                .await
+                .iter()
+                .map(|(key, (name, lookup))| (key, name.as_str(), lookup.file_len())),
+        );
+
+        files.scope(f)
+    }
+}
+
+#[cfg(all(test, feature = "terminal", not(feature = "smol")))]
+mod tests {
+    use crate::span::Spanned;
+
+    use super::*;
+
+    fn reporter() -> (TerminalReporter<Vec<u8>>, LookupKey) {
+        let mut reporter = TerminalReporter::new(Vec::new());
+        let key = reporter.register_file("main.rs", "let x = ;");
+        (reporter, key)
+    }
+
+    fn from_json<T: for<'de> Deserialize<'de>>(json: &str) -> Result<T, String> {
+        let (reporter, _) = reporter();
+        reporter
+            .serde_scope(|| serde_json::from_str(json))
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn round_trip() {
+        let (reporter, key) = reporter();
+        let span = Span::new(key, 8..9);
+
+        let mut diagnostic = Diagnostic::spanned_error(span, "expected expression")
+            .with_code(Some("E0001"))
+            .with_lint(Some("syntax"));
+        diagnostic.add_spanned_note("in this statement", Span::new(key, 0..9));
+        diagnostic.add_help("add an expression");
+
+        let json = reporter
+            .serde_scope(|| serde_json::to_string(&diagnostic))
+            .unwrap();
+        assert_eq!(from_json::<Diagnostic>(&json), Ok(diagnostic));
+
+        let json = reporter
+            .serde_scope(|| serde_json::to_string(&span))
+            .unwrap();
+        assert_eq!(json, r#"{"file":"main.rs","start":8,"end":9}"#);
+        assert_eq!(from_json::<Span>(&json), Ok(span));
+
+        let spanned = Spanned::new(';', span);
+        let json = reporter
+            .serde_scope(|| serde_json::to_string(&spanned))
+            .unwrap();
+        let deserialized: Spanned<char> = from_json(&json).unwrap();
+        assert_eq!(*deserialized, ';');
+        assert_eq!(deserialized.span(), span);
+    }
+
+    #[test]
+    fn invalid_spans() {
+        assert_eq!(
+            from_json::<Span>(r#"{"file":"lib.rs","start":0,"end":1}"#),
+            Err("no file named `lib.rs` is registered".to_owned())
+        );
+        assert_eq!(
+            from_json::<Span>(r#"{"file":"main.rs","start":5,"end":4}"#),
+            Err("span 5..4 ends before it starts".to_owned())
+        );
+        assert_eq!(
+            from_json::<Span>(r#"{"file":"main.rs","start":9,"end":10}"#),
+            Err("span 9..10 lies outside of `main.rs`, which is 9 bytes long".to_owned())
+        );
+        assert!(from_json::<Span>(r#"{"file":"main.rs","start":9,"end":9}"#).is_ok());
+    }
+
+    #[test]
+    fn outside_of_a_scope() {
+        let (_, key) = reporter();
+        let err = serde_json::to_string(&Span::new(key, 0..1)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "spans can only be serialized within `TerminalReporter::serde_scope`"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! A [`tracing_subscriber`] layer that reports tagged events as diagnostics.
+
+use std::fmt;
+
+use anstream::stream::{AsLockedWrite, RawStream};
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::{
+    diagnostic::{Diagnostic, Level},
+    reporter::SharedReporter,
+};
+
+impl From<tracing::Level> for Level {
+    fn from(level: tracing::Level) -> Level {
+        match level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+/// A [`Layer`] that reports events carrying `nurse.*` fields into a [`SharedReporter`].
+///
+/// This lets internal passes instrumented with `tracing` surface diagnostics to the user
+/// without passing a reporter through every function.
+/// Events without any `nurse.*` fields are ignored.
+///
+/// The level of the event becomes the level of the diagnostic,
+/// with `TRACE` events becoming [`Debug`](Level::Debug) diagnostics,
+/// and the event's message becomes the diagnostic's message.
+/// The following fields are recognized:
+///
+/// | Field         | Meaning                                                        |
+/// |---------------|----------------------------------------------------------------|
+/// | `nurse.file`  | The name of the registered file the diagnostic refers to       |
+/// | `nurse.start` | The byte offset the span starts at                             |
+/// | `nurse.end`   | The byte offset the span ends at, defaulting to `nurse.start`  |
+/// | `nurse.code`  | The [code](Diagnostic::code) of the diagnostic                 |
+/// | `nurse.help`  | A [help message](Diagnostic::with_help) for the diagnostic     |
+/// | `nurse.note`  | A [note](Diagnostic::add_note) for the diagnostic             |
+///
+/// If no file with the given name is registered,
+/// or the span doesn't lie within it or ends before it starts,
+/// the diagnostic is reported without a span and notes the name of the file instead.
+///
+/// Events recorded while the reporter is locked, whether by another thread
+/// or by the recording thread itself, e.g. from a `Display` implementation called while printing,
+/// are held back until the reporter is next [locked](SharedReporter::lock) rather than waiting for it.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::{DiagnosticLayer, SharedReporter};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let reporter = SharedReporter::new(TerminalReporter::default());
+/// reporter.register_file("main.rs", "let x = ;");
+///
+/// let subscriber = tracing_subscriber::registry().with(DiagnosticLayer::new(reporter.clone()));
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::error!(nurse.file = "main.rs", nurse.start = 8, "expected expression");
+///     tracing::warn!("not a diagnostic");
+/// });
+///
+/// reporter.emit_all()?;
+/// assert_eq!(reporter.lock().error_count(), 1);
+/// assert_eq!(reporter.lock().warning_count(), 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DiagnosticLayer<T: RawStream + AsLockedWrite> {
+    reporter: SharedReporter<T>,
+}
+
+impl<T: RawStream + AsLockedWrite + Send> DiagnosticLayer<T> {
+    /// Creates a layer reporting diagnostics into the given `reporter`.
+    #[inline]
+    pub fn new(reporter: SharedReporter<T>) -> DiagnosticLayer<T> {
+        DiagnosticLayer { reporter }
+    }
+
+    /// Gets the reporter that diagnostics are reported into.
+    #[inline]
+    pub fn reporter(&self) -> &SharedReporter<T> {
+        &self.reporter
+    }
+}
+
+impl<S, T> Layer<S> for DiagnosticLayer<T>
+where
+    S: Subscriber,
+    T: RawStream + AsLockedWrite + Send + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+
+        if !fields.tagged {
+            return;
+        }
+
+        let level = (*event.metadata().level()).into();
+
+        // Locking the reporter would deadlock if the event was recorded while it's locked
+        self.reporter.lock_or_defer(move |reporter| {
+            let mut diagnostic =
+                Diagnostic::error(fields.message.unwrap_or_default()).with_code(fields.code);
+            diagnostic.level = level;
+
+            if let (Some(file), Some(start)) = (fields.file, fields.start) {
+                let span = reporter.find_file(&file).and_then(|lookup| {
+                    reporter.checked_span(lookup, start, fields.end.unwrap_or(start))
+                });
+
+                match span {
+                    Some(span) => diagnostic.set_span(Some(span)),
+                    None => diagnostic.add_note(format!("in `{file}`")),
+                };
+            }
+
+            for note in fields.notes {
+                diagnostic.add_note(note);
+            }
+            for help in fields.help {
+                diagnostic.add_help(help);
+            }
+
+            reporter.report(diagnostic);
+        });
+    }
+}
+
+/// The fields of an event that are relevant to its diagnostic.
+#[derive(Debug, Default)]
+struct Fields {
+    /// Whether any `nurse.*` fields were recorded
+    tagged: bool,
+    message: Option<String>,
+    file: Option<String>,
+    start: Option<usize>,
+    end: Option<usize>,
+    code: Option<String>,
+    notes: Vec<String>,
+    help: Vec<String>,
+}
+
+impl Fields {
+    fn record_string(&mut self, field: &Field, value: String) {
+        match field.name() {
+            "message" => self.message = Some(value),
+            "nurse.file" => self.file = Some(value),
+            "nurse.code" => self.code = Some(value),
+            "nurse.note" => self.notes.push(value),
+            "nurse.help" => self.help.push(value),
+            "nurse.start" => self.start = value.parse().ok(),
+            "nurse.end" => self.end = value.parse().ok(),
+            _ => return,
+        }
+
+        self.tagged |= field.name().starts_with("nurse.");
+    }
+}
+
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        let offset = usize::try_from(value).ok();
+        match field.name() {
+            "nurse.start" => self.start = offset,
+            "nurse.end" => self.end = offset,
+            _ => return self.record_string(field, value.to_string()),
+        }
+
+        self.tagged = true;
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        match u64::try_from(value) {
+            Ok(value) => self.record_u64(field, value),
+            Err(_) => self.record_string(field, value.to_string()),
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_string(field, value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_string(field, format!("{value:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+    use crate::{reporter::TerminalReporter, span::Span};
+
+    #[test]
+    fn spans_outside_the_file_are_dropped() {
+        let reporter = SharedReporter::new(TerminalReporter::new(Vec::new()));
+        let file = reporter.register_file("a.txt", "let x = ;\nü\n");
+
+        let subscriber =
+            tracing_subscriber::registry().with(DiagnosticLayer::new(reporter.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(
+                nurse.file = "a.txt",
+                nurse.start = 8,
+                nurse.end = 9,
+                "valid"
+            );
+            tracing::error!(
+                nurse.file = "a.txt",
+                nurse.start = 12,
+                "at the end of the file"
+            );
+            tracing::error!(
+                nurse.file = "a.txt",
+                nurse.start = 100,
+                nurse.end = 105,
+                "past"
+            );
+            tracing::error!(
+                nurse.file = "a.txt",
+                nurse.start = 8,
+                nurse.end = 4,
+                "backwards"
+            );
+            tracing::error!(nurse.file = "a.txt", nurse.start = 11, "inside a character");
+            tracing::error!(nurse.file = "b.txt", nurse.start = 0, "unknown file");
+        });
+
+        let reporter = reporter.try_unwrap().unwrap();
+        let spans: Vec<_> = reporter
+            .diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let note = diagnostic.notes().first().map(|note| note.value());
+                (diagnostic.message(), diagnostic.span(), note)
+            })
+            .collect();
+
+        assert_eq!(
+            spans,
+            [
+                ("valid", Some(Span::new(file, 8..9)), None),
+                (
+                    "at the end of the file",
+                    Some(Span::new(file, 12..12)),
+                    None
+                ),
+                ("past", None, Some("in `a.txt`")),
+                ("backwards", None, Some("in `a.txt`")),
+                ("inside a character", None, Some("in `a.txt`")),
+                ("unknown file", None, Some("in `b.txt`")),
+            ]
+        );
+    }
+
+    #[test]
+    fn events_while_locked_are_held_back() {
+        let reporter = SharedReporter::new(TerminalReporter::new(Vec::new()));
+        reporter.register_file("a.txt", "let x = ;\n");
+
+        let subscriber =
+            tracing_subscriber::registry().with(DiagnosticLayer::new(reporter.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            // Recording an event while holding the lock doesn't deadlock
+            let locked = reporter.lock();
+            tracing::error!(nurse.file = "a.txt", nurse.start = 8, "expected expression");
+            assert!(locked.diagnostics.is_empty());
+        });
+
+        assert_eq!(reporter.lock().diagnostics.len(), 1);
+    }
+}
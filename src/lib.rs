@@ -2,13 +2,30 @@
 #![warn(missing_docs)]
 
 mod diagnostic;
+#[cfg(any(
+    feature = "miette",
+    feature = "codespan",
+    feature = "tracing",
+    feature = "log",
+    feature = "serde",
+    feature = "gcc"
+))]
+mod interop;
 mod lookup;
 mod reporter;
 mod span;
 
 pub use diagnostic::*;
+#[cfg(all(feature = "tracing", not(feature = "smol")))]
+pub use interop::DiagnosticLayer;
+#[cfg(all(feature = "log", not(feature = "smol")))]
+pub use interop::DiagnosticLogger;
+pub use lookup::{Location, LocationError};
 pub use reporter::*;
 pub use span::*;
+#[cfg(all(feature = "log", not(feature = "smol")))]
+#[doc(hidden)]
+pub use {::log as __log, interop::log::lookup_id as __lookup_id};
 
 /// Re-exports most of the commonly used st ructures and macros from the crate.
 /// Designed to be used as a glob import (e.g. `use nurse::prelude::*`)
@@ -1,6 +1,6 @@
 //! Containing utilities surrounding [`Lookup`].
 
-use std::{cmp::Ordering, ops::Range};
+use std::{cmp::Ordering, error::Error, fmt, ops::Range};
 
 /// Internal file lookup-table used in [`Reporter`](crate::reporter::Reporter)s
 /// to locate lines, columns, and text.
@@ -55,26 +55,88 @@ impl Lookup {
         let start_line = self.line_n(span.start);
         let next_start = *self.heads.get(start_line + 1).unwrap_or(&self.source.len());
 
-        if span.end <= next_start {
+        if span.end <= span.start || span.end <= next_start {
             // Check if the span ends on the same line
             start_line..start_line + 1
         } else {
             // Otherwise perform a binary search through the rest of the lines.
             match self.heads[start_line..].binary_search(&(span.end - 1)) {
-                Ok(end_line) => start_line..end_line + 1,
-                Err(insert) => start_line..insert,
+                Ok(end_line) => start_line..start_line + end_line + 1,
+                Err(insert) => start_line..start_line + insert,
             }
         }
     }
 
+    // Only used by some of the optional integrations
+    #[allow(dead_code)]
+    #[inline]
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    #[inline]
+    pub fn line_start(&self, line: usize) -> usize {
+        self.heads[line]
+    }
+
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.heads.len()
+    }
+
     pub fn file_len(&self) -> usize {
-        return self.source.len();
+        self.source.len()
+    }
+
+    /// The index used for spans pointing at the end of the file.
+    ///
+    /// A trailing line break is skipped over,
+    /// keeping the end of the file on its last line of text.
+    pub fn eof(&self) -> usize {
+        self.source
+            .strip_suffix('\n')
+            .map(|source| source.strip_suffix('\r').unwrap_or(source))
+            .map_or(self.file_len(), str::len)
+    }
+
+    /// Converts a line-column location into a character index,
+    /// the inverse of [`line_col`](Lookup::line_col).
+    ///
+    /// The column may point one past the last character of the line,
+    /// which allows for spans ending at a line break or at the end of the file.
+    pub fn offset(&self, location: Location) -> Result<usize, LocationError> {
+        let head = *self
+            .heads
+            .get(location.line)
+            .ok_or(LocationError::LineOutOfRange {
+                line: location.line,
+                lines: self.heads.len(),
+            })?;
+
+        let length = self
+            .line(location.line)
+            .trim_end_matches(['\n', '\r'])
+            .len();
+        if location.column > length {
+            return Err(LocationError::ColumnOutOfRange {
+                column: location.column,
+                length,
+            });
+        }
+
+        let index = head + location.column;
+        if !self.source.is_char_boundary(index) {
+            return Err(LocationError::NotCharBoundary(location));
+        }
+
+        Ok(index)
     }
 }
 
 /// A location within a file,
-/// using line-column indexing as opposed to character indices.
+/// using zero-indexed line-column indexing as opposed to character indices.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
     /// The line of the character
     pub line: usize,
@@ -95,3 +157,124 @@ impl PartialOrd for Location {
         )
     }
 }
+
+/// An error produced when converting a [`Location`] into a character index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationError {
+    /// The line is past the end of the file.
+    LineOutOfRange {
+        /// The requested line
+        line: usize,
+        /// The number of lines in the file
+        lines: usize,
+    },
+    /// The column is past the end of the line.
+    ColumnOutOfRange {
+        /// The requested column
+        column: usize,
+        /// The length of the line, excluding the line break
+        length: usize,
+    },
+    /// The location falls inside of a multi-byte character.
+    NotCharBoundary(Location),
+    /// The start of a range is located after its end.
+    Inverted {
+        /// The start of the range
+        start: Location,
+        /// The end of the range
+        end: Location,
+    },
+}
+
+impl fmt::Display for LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocationError::LineOutOfRange { line, lines } => {
+                write!(
+                    f,
+                    "line {line} is out of range for a file with {lines} lines"
+                )
+            }
+            LocationError::ColumnOutOfRange { column, length } => {
+                write!(
+                    f,
+                    "column {column} is out of range for a line of length {length}"
+                )
+            }
+            LocationError::NotCharBoundary(location) => write!(
+                f,
+                "{}:{} is not on a character boundary",
+                location.line, location.column
+            ),
+            LocationError::Inverted { start, end } => write!(
+                f,
+                "range start {}:{} is after its end {}:{}",
+                start.line, start.column, end.line, end.column
+            ),
+        }
+    }
+}
+
+impl Error for LocationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_span_lines() {
+        let lookup = Lookup::new("foo\nbar\n".to_owned());
+
+        assert_eq!(lookup.lines(0..0), 0..1);
+        assert_eq!(lookup.lines(3..3), 0..1);
+        assert_eq!(lookup.lines(4..4), 1..2);
+        assert_eq!(lookup.lines(8..8), 2..3);
+    }
+
+    #[test]
+    fn multiline_span_lines() {
+        let lookup = Lookup::new("foo\nbar\nbaz".to_owned());
+
+        assert_eq!(lookup.lines(0..4), 0..1);
+        assert_eq!(lookup.lines(0..5), 0..2);
+        assert_eq!(lookup.lines(2..11), 0..3);
+    }
+
+    #[test]
+    fn eof() {
+        assert_eq!(Lookup::new(String::new()).eof(), 0);
+        assert_eq!(Lookup::new("foo".to_owned()).eof(), 3);
+        assert_eq!(Lookup::new("foo\n".to_owned()).eof(), 3);
+        assert_eq!(Lookup::new("foo\r\n".to_owned()).eof(), 3);
+
+        let lookup = Lookup::new("foo\nbar\n".to_owned());
+        assert_eq!(lookup.line_col(lookup.eof()), (1, 3));
+    }
+
+    #[test]
+    fn offset_round_trip() {
+        let lookup = Lookup::new("foo\nbar\n".to_owned());
+
+        for index in 0..lookup.file_len() {
+            let (line, column) = lookup.line_col(index);
+            assert_eq!(lookup.offset(Location { line, column }), Ok(index));
+        }
+    }
+
+    #[test]
+    fn offset_out_of_range() {
+        let lookup = Lookup::new("foo\nbar".to_owned());
+
+        assert_eq!(
+            lookup.offset(Location { line: 2, column: 0 }),
+            Err(LocationError::LineOutOfRange { line: 2, lines: 2 })
+        );
+        assert_eq!(
+            lookup.offset(Location { line: 0, column: 4 }),
+            Err(LocationError::ColumnOutOfRange {
+                column: 4,
+                length: 3
+            })
+        );
+    }
+}
@@ -3,10 +3,36 @@ mod lsp;
 #[cfg(feature = "lsp")]
 pub use lsp::*;
 
+#[cfg(all(feature = "terminal", not(feature = "smol")))]
+mod buffer;
+#[cfg(all(feature = "cache", not(feature = "smol")))]
+mod cache;
+#[cfg(all(feature = "terminal", not(feature = "smol")))]
+pub use buffer::DiagnosticBuffer;
+#[cfg(all(feature = "cache", not(feature = "smol")))]
+pub use cache::DiagnosticCache;
+#[cfg(feature = "terminal")]
+mod layout;
+#[cfg(all(feature = "terminal", not(feature = "smol")))]
+mod shared;
+#[cfg(all(feature = "terminal", not(feature = "smol")))]
+pub use shared::SharedReporter;
+#[cfg(feature = "terminal")]
+mod snippet;
+#[cfg(feature = "terminal")]
+pub use snippet::SnippetWindow;
+#[cfg(feature = "terminal")]
+mod suppression;
+#[cfg(feature = "terminal")]
+pub use suppression::Suppressions;
 #[cfg(feature = "terminal")]
 mod terminal;
 #[cfg(feature = "terminal")]
+mod theme;
+#[cfg(feature = "terminal")]
 pub use terminal::*;
+#[cfg(feature = "terminal")]
+pub use theme::{Color, Glyphs, TextStyle, Theme};
 
 use slotmap::new_key_type;
 #[cfg(feature = "terminal")]
@@ -0,0 +1,193 @@
+//! Scoped buffers of diagnostics that can be committed to or discarded from a [`TerminalReporter`].
+
+use anstream::stream::{AsLockedWrite, RawStream};
+
+use crate::diagnostic::Diagnostic;
+
+use super::{ReportFlow, TerminalReporter};
+
+/// A scoped buffer of diagnostics, created by [`TerminalReporter::buffer`].
+///
+/// Diagnostics reported into a buffer are held back until the buffer is [committed](DiagnosticBuffer::commit)
+/// into its parent, or dropped when it is [discarded](DiagnosticBuffer::discard).
+/// This is useful for speculative parsing,
+/// where the diagnostics of a failed attempt should disappear when backtracking.
+///
+/// Buffers borrow their reporter,
+/// so spans created from its registered files stay valid,
+/// and can be nested with [`buffer`](DiagnosticBuffer::buffer).
+/// Dropping a buffer without committing it discards its diagnostics.
+///
+/// Buffers are only available without the `smol` feature.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// let mut reporter = TerminalReporter::default();
+/// let file = reporter.register_file("example.txt", "(1 + 2");
+///
+/// let mut attempt = reporter.buffer();
+/// attempt.report(error!(Span::new(file, 0..1), "unmatched parenthesis"));
+///
+/// let mut nested = attempt.buffer();
+/// nested.report(error!(Span::new(file, 6..6), "expected `)`"));
+/// nested.discard();
+///
+/// assert_eq!(attempt.len(), 1);
+/// attempt.commit();
+///
+/// assert!(reporter.has_errors());
+/// ```
+#[derive(Debug)]
+pub struct DiagnosticBuffer<'a, T: RawStream + AsLockedWrite + Send> {
+    reporter: &'a mut TerminalReporter<T>,
+    parent: Option<&'a mut Vec<Diagnostic>>,
+    /// The number of errors held by the buffers this one is nested in
+    pending: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, T: RawStream + AsLockedWrite + Send> DiagnosticBuffer<'a, T> {
+    pub(crate) fn new(reporter: &'a mut TerminalReporter<T>) -> DiagnosticBuffer<'a, T> {
+        DiagnosticBuffer {
+            reporter,
+            parent: None,
+            pending: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Gets the reporter the buffer will eventually be committed to,
+    /// for example to look up the [`location`](TerminalReporter::location) of a span.
+    #[inline]
+    pub fn reporter(&self) -> &TerminalReporter<T> {
+        self.reporter
+    }
+
+    /// Creates a buffer nested in this one,
+    /// which commits its diagnostics into this buffer instead of the reporter.
+    pub fn buffer(&mut self) -> DiagnosticBuffer<'_, T> {
+        let pending = self.pending + self.error_count();
+
+        DiagnosticBuffer {
+            reporter: self.reporter,
+            parent: Some(&mut self.diagnostics),
+            pending,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Adds the provided `diagnostic` to the buffer.
+    ///
+    /// Returns [`ReportFlow::Abort`] once the errors reported to the reporter,
+    /// together with the errors held by this buffer and the buffers it is nested in,
+    /// reach the reporter's [maximum error count](TerminalReporter::set_max_errors).
+    /// Buffered errors are counted by their level before the reporter's [policy](TerminalReporter::set_policy) is applied.
+    #[inline]
+    pub fn report(&mut self, diagnostic: Diagnostic) -> ReportFlow {
+        self.diagnostics.push(diagnostic);
+        self.signal()
+    }
+
+    /// Adds the provided list of `diagnostics` to the buffer.
+    ///
+    /// Returns [`ReportFlow::Abort`] under the same conditions as [`report`](DiagnosticBuffer::report).
+    #[inline]
+    pub fn report_all(&mut self, diagnostics: Vec<Diagnostic>) -> ReportFlow {
+        self.diagnostics.extend(diagnostics);
+        self.signal()
+    }
+
+    fn signal(&self) -> ReportFlow {
+        self.reporter
+            .signal_pending(self.pending + self.error_count())
+    }
+
+    fn error_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.is_error())
+            .count()
+    }
+
+    /// Gets the diagnostics held in the buffer, in the order they were reported.
+    #[inline]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the number of diagnostics held in the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.diagnostics.len()
+    }
+
+    /// Returns `true` if there are no diagnostics held in the buffer,
+    /// otherwise returns `false`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Returns `true` if any diagnostics held in the buffer are of level [`Error`](crate::Level::Error),
+    /// otherwise returns `false`.
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.is_error())
+    }
+
+    /// Moves the buffered diagnostics into the parent buffer,
+    /// or reports them to the reporter if this buffer isn't nested.
+    ///
+    /// Returns the signal of [`report_all`](TerminalReporter::report_all) when reporting to the reporter,
+    /// and [`ReportFlow::Continue`] when committing to a parent buffer.
+    pub fn commit(self) -> ReportFlow {
+        match self.parent {
+            Some(parent) => {
+                parent.extend(self.diagnostics);
+                ReportFlow::Continue
+            }
+            None => self.reporter.report_all(self.diagnostics),
+        }
+    }
+
+    /// Drops the buffered diagnostics without reporting them.
+    #[inline]
+    pub fn discard(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_signals_max_errors() {
+        let mut reporter = TerminalReporter::new(Vec::new()).with_max_errors(Some(3));
+        reporter.report(Diagnostic::error("committed"));
+
+        let mut attempt = reporter.buffer();
+        assert_eq!(
+            attempt.report(Diagnostic::warning("warning")),
+            ReportFlow::Continue
+        );
+        assert_eq!(
+            attempt.report(Diagnostic::error("first")),
+            ReportFlow::Continue
+        );
+
+        let mut nested = attempt.buffer();
+        assert_eq!(
+            nested.report(Diagnostic::error("second")),
+            ReportFlow::Abort
+        );
+        nested.discard();
+
+        assert_eq!(
+            attempt.report_all(vec![Diagnostic::error("third")]),
+            ReportFlow::Abort
+        );
+        assert_eq!(attempt.commit(), ReportFlow::Abort);
+    }
+}
@@ -0,0 +1,404 @@
+//! An on-disk cache of diagnostics, keyed by the files they were produced for.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufReader, BufWriter, ErrorKind},
+    path::Path,
+};
+
+use anstream::stream::{AsLockedWrite, RawStream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    diagnostic::{Diagnostic, Level, Note},
+    interop::serde::intern,
+    lookup::Lookup,
+    span::Span,
+};
+
+use super::{LookupKey, TerminalReporter};
+
+/// The version of the on-disk format,
+/// which invalidates caches written by incompatible versions of the crate.
+const VERSION: u32 = 2;
+
+/// A cache of the diagnostics produced for files, keyed by the registered name of each file.
+///
+/// Each entry holds a hash of the contents it was produced for,
+/// and is only used while the file's contents still match.
+/// When a registered file is unchanged since the last run,
+/// its previously produced diagnostics can be replayed instead of recomputing them.
+/// Spans are stored relative to the file they were produced for,
+/// and are re-attached to the file's current [`LookupKey`] when retrieved.
+///
+/// Only spans within the cached file are kept,
+/// since changes to other files aren't covered by its hash.
+/// The [source](std::error::Error::source) of each diagnostic is not cached.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::DiagnosticCache;
+///
+/// # fn main() -> std::io::Result<()> {
+/// # let dir = std::env::temp_dir().join(format!("nurse-cache-doc-{}", std::process::id()));
+/// # std::fs::create_dir_all(&dir)?;
+/// let path = dir.join("diagnostics.json");
+///
+/// let mut reporter = TerminalReporter::default();
+/// let file = reporter.register_file("main.rs", "let x = ;");
+///
+/// let mut cache = DiagnosticCache::load(&path)?;
+/// let diagnostics = match cache.get(&reporter, file) {
+///     Some(diagnostics) => diagnostics,
+///     None => {
+///         let diagnostics = vec![error!(Span::new(file, 8..9), "expected expression")];
+///         cache.insert(&reporter, file, &diagnostics);
+///         diagnostics
+///     }
+/// };
+/// reporter.report_all(diagnostics);
+/// cache.save(&path)?;
+///
+/// // On the next run, the unchanged file is found in the cache
+/// let mut reporter = TerminalReporter::default();
+/// let file = reporter.register_file("main.rs", "let x = ;");
+///
+/// let cached = DiagnosticCache::load(&path)?.get(&reporter, file).unwrap();
+/// assert_eq!(cached[0].span(), Some(Span::new(file, 8..9)));
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DiagnosticCache {
+    entries: HashMap<String, CachedFile>,
+    /// The names that have been looked up or inserted since the cache was loaded
+    used: HashSet<String>,
+}
+
+impl DiagnosticCache {
+    /// Creates an empty cache.
+    #[inline]
+    pub fn new() -> DiagnosticCache {
+        DiagnosticCache::default()
+    }
+
+    /// Loads a cache from the file at `path`.
+    ///
+    /// Returns an empty cache if the file does not exist,
+    /// or if it was written by an incompatible version of the crate.
+    ///
+    /// ## Errors
+    ///
+    /// This function will fail if the file cannot be read or is not a valid cache.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<DiagnosticCache> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(DiagnosticCache::new()),
+            Err(err) => return Err(err),
+        };
+
+        // The entries are only decoded once the version is known to match,
+        // since their format differs between versions
+        let stored: StoredCache<serde_json::Value> = serde_json::from_reader(BufReader::new(file))?;
+        if stored.version != VERSION {
+            return Ok(DiagnosticCache::new());
+        }
+
+        Ok(DiagnosticCache {
+            entries: serde_json::from_value(stored.entries)?,
+            used: HashSet::new(),
+        })
+    }
+
+    /// Writes the cache to the file at `path`, replacing its previous contents.
+    ///
+    /// ## Errors
+    ///
+    /// This function will fail if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(path)?);
+        serde_json::to_writer(
+            &mut writer,
+            &StoredCache {
+                version: VERSION,
+                entries: &self.entries,
+            },
+        )?;
+
+        io::Write::flush(&mut writer)
+    }
+
+    /// Gets the cached diagnostics for the file referred to by `key`,
+    /// returning `None` if no diagnostics were cached for its name and current contents.
+    ///
+    /// The returned diagnostics span the file referred to by `key`.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if `key` is not registered with `reporter`.
+    pub fn get<T: RawStream + AsLockedWrite + Send>(
+        &mut self,
+        reporter: &TerminalReporter<T>,
+        key: LookupKey,
+    ) -> Option<Vec<Diagnostic>> {
+        let (name, lookup) = file(reporter, key);
+        self.used.insert(name.to_owned());
+
+        self.entries
+            .get(name)
+            .filter(|entry| entry.hash == hash(lookup))
+            .map(|entry| {
+                entry
+                    .diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic.attach(key))
+                    .collect()
+            })
+    }
+
+    /// Caches `diagnostics` for the current contents of the file referred to by `key`,
+    /// replacing any diagnostics previously cached for a file with the same name.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if `key` is not registered with `reporter`.
+    pub fn insert<T: RawStream + AsLockedWrite + Send>(
+        &mut self,
+        reporter: &TerminalReporter<T>,
+        key: LookupKey,
+        diagnostics: &[Diagnostic],
+    ) {
+        let (name, lookup) = file(reporter, key);
+        self.used.insert(name.to_owned());
+
+        self.entries.insert(
+            name.to_owned(),
+            CachedFile {
+                hash: hash(lookup),
+                diagnostics: diagnostics
+                    .iter()
+                    .map(|diagnostic| CachedDiagnostic::detach(diagnostic, key))
+                    .collect(),
+            },
+        );
+    }
+
+    /// Removes every entry that hasn't been looked up or inserted since the cache was loaded,
+    /// such as those for files that no longer exist.
+    pub fn retain_used(&mut self) {
+        self.entries.retain(|name, _| self.used.contains(name));
+    }
+
+    /// Removes every entry from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used.clear();
+    }
+
+    /// Returns the number of files with cached diagnostics.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no diagnostics are cached,
+    /// otherwise returns `false`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn file<T: RawStream + AsLockedWrite + Send>(
+    reporter: &TerminalReporter<T>,
+    key: LookupKey,
+) -> (&str, &Lookup) {
+    reporter
+        .file(key)
+        .expect("lookup key should be registered with the reporter")
+}
+
+/// Hashes the contents of a file with 64-bit FNV-1a,
+/// which unlike the standard library's hasher is stable between versions and platforms.
+fn hash(lookup: &Lookup) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    lookup.source().bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// The on-disk form of a [`DiagnosticCache`].
+#[derive(Serialize, Deserialize)]
+struct StoredCache<E> {
+    version: u32,
+    entries: E,
+}
+
+/// The diagnostics cached for a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedFile {
+    /// The hash of the contents the diagnostics were produced for
+    hash: u64,
+    diagnostics: Vec<CachedDiagnostic>,
+}
+
+/// A diagnostic with spans relative to the file it was cached for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedDiagnostic {
+    level: Level,
+    message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<CachedNote>,
+    #[serde(default)]
+    span: Option<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CachedNote {
+    value: String,
+    #[serde(default)]
+    span: Option<(usize, usize)>,
+    #[serde(default)]
+    help: bool,
+}
+
+/// Gets the range of `span` if it lies within the file referred to by `key`.
+fn detach_span(span: Option<Span>, key: LookupKey) -> Option<(usize, usize)> {
+    span.filter(|span| span.lookup == key)
+        .map(|span| (span.start, span.end))
+}
+
+fn attach_span(span: Option<(usize, usize)>, key: LookupKey) -> Option<Span> {
+    span.map(|(start, end)| Span {
+        lookup: key,
+        start,
+        end,
+    })
+}
+
+impl CachedDiagnostic {
+    fn detach(diagnostic: &Diagnostic, key: LookupKey) -> CachedDiagnostic {
+        CachedDiagnostic {
+            level: diagnostic.level,
+            message: diagnostic.message.clone(),
+            notes: diagnostic
+                .notes
+                .iter()
+                .map(|note| CachedNote {
+                    value: note.value.clone(),
+                    span: detach_span(note.span, key),
+                    help: note.help,
+                })
+                .collect(),
+            span: detach_span(diagnostic.span, key),
+            lint: diagnostic.lint.map(str::to_owned),
+            code: diagnostic.code.clone(),
+        }
+    }
+
+    fn attach(&self, key: LookupKey) -> Diagnostic {
+        Diagnostic {
+            level: self.level,
+            message: self.message.clone(),
+            notes: self
+                .notes
+                .iter()
+                .map(|note| Note {
+                    value: note.value.clone(),
+                    span: attach_span(note.span, key),
+                    help: note.help,
+                })
+                .collect(),
+            span: attach_span(self.span, key),
+            lint: self.lint.clone().map(intern),
+            code: self.code.clone(),
+            source: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_depends_on_contents() {
+        let a = hash(&Lookup::new("let x = 1;".to_owned()));
+        let b = hash(&Lookup::new("let x = 2;".to_owned()));
+
+        assert_ne!(a, b);
+        assert_eq!(a, hash(&Lookup::new("let x = 1;".to_owned())));
+    }
+
+    #[test]
+    fn round_trip_keeps_only_local_spans() {
+        let mut reporter = TerminalReporter::default();
+        let main = reporter.register_file("main.rs", "mod other;");
+        let other = reporter.register_file("other.rs", "fn f() {}");
+
+        let diagnostic = Diagnostic::spanned_warning(Span::new(main, 0..3), "unused module")
+            .with_spanned_note(Span::new(other, 3..4), "defined here")
+            .with_lint(Some("unused_module"));
+
+        let mut cache = DiagnosticCache::new();
+        cache.insert(&reporter, main, std::slice::from_ref(&diagnostic));
+
+        let mut fresh = TerminalReporter::default();
+        let key = fresh.register_file("main.rs", "mod other;");
+        let cached = cache.get(&fresh, key).unwrap();
+
+        assert_eq!(cached[0].span(), Some(Span::new(key, 0..3)));
+        assert_eq!(cached[0].notes()[0].span(), None);
+        assert_eq!(cached[0].lint(), diagnostic.lint());
+    }
+
+    #[test]
+    fn old_versions_load_empty() {
+        let path = std::env::temp_dir().join(format!("nurse-cache-v1-{}.json", std::process::id()));
+        // Version 1 keyed the diagnostics by the hash of each file's contents
+        fs::write(
+            &path,
+            r#"{"version":1,"entries":{"1234":[{"level":"Warn","message":"unused label"}]}}"#,
+        )
+        .unwrap();
+
+        let cache = DiagnosticCache::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(cache.unwrap().is_empty());
+    }
+
+    #[test]
+    fn files_with_the_same_contents() {
+        let mut reporter = TerminalReporter::default();
+        let a = reporter.register_file("a.h", "");
+        let b = reporter.register_file("b.h", "");
+
+        let mut cache = DiagnosticCache::new();
+        cache.insert(&reporter, a, &[Diagnostic::warning("empty header `a.h`")]);
+        cache.insert(&reporter, b, &[Diagnostic::warning("empty header `b.h`")]);
+        assert_eq!(cache.len(), 2);
+
+        let message = |key| {
+            cache.clone().get(&reporter, key).unwrap()[0]
+                .message()
+                .to_owned()
+        };
+        assert_eq!(message(a), "empty header `a.h`");
+        assert_eq!(message(b), "empty header `b.h`");
+
+        // A file with the same name but different contents misses
+        let mut changed = TerminalReporter::default();
+        let a = changed.register_file("a.h", "#pragma once");
+        assert!(cache.get(&changed, a).is_none());
+    }
+}
@@ -0,0 +1,338 @@
+//! Layout of the underlines and labels annotating a single line of source.
+//!
+//! Labels are stacked in the same way as `rustc`:
+//! the rightmost label is placed inline after its underline when nothing overlaps it,
+//! while every other label hangs below the line from a vertical connector.
+//!
+//! ```text
+//! foo(bar, baz)
+//! ‾‾‾ --- --- third
+//! │   │
+//! │   second
+//! first
+//! ```
+
+use std::fmt;
+
+use super::theme::Glyphs;
+
+/// The style of a single cell in an annotation [`Row`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Style {
+    /// Unstyled whitespace.
+    Plain,
+    /// Belongs to the primary annotation of a diagnostic.
+    Primary,
+    /// Belongs to a secondary annotation, such as a spanned note.
+    Secondary,
+}
+
+/// A range of columns on a single line, optionally labelled with a message.
+///
+/// Columns are counted in characters from the start of the line.
+/// Zero-width annotations mark an insertion point,
+/// drawn as a single caret in the column at `start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Annotation<'a> {
+    pub start: usize,
+    pub end: usize,
+    pub label: Option<&'a str>,
+    pub primary: bool,
+}
+
+impl Annotation<'_> {
+    #[inline]
+    fn style(&self) -> Style {
+        if self.primary {
+            Style::Primary
+        } else {
+            Style::Secondary
+        }
+    }
+
+    /// The end of the columns occupied by the annotation's underline.
+    #[inline]
+    fn visible_end(&self) -> usize {
+        self.end.max(self.start + 1)
+    }
+
+    #[inline]
+    fn underline(&self, glyphs: &Glyphs) -> char {
+        match (self.end <= self.start, self.primary) {
+            (true, _) => glyphs.insertion_point,
+            (false, true) => glyphs.primary_underline,
+            (false, false) => glyphs.secondary_underline,
+        }
+    }
+}
+
+/// A single row of styled characters printed beneath a line of source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Row {
+    cells: Vec<(char, Style)>,
+}
+
+impl Row {
+    /// Places `c` at `column`, padding the row with spaces if necessary.
+    pub fn put(&mut self, column: usize, c: char, style: Style) {
+        if self.cells.len() <= column {
+            self.cells.resize(column + 1, (' ', Style::Plain));
+        }
+
+        self.cells[column] = (c, style);
+    }
+
+    /// Checks whether the cell at `column` is unstyled whitespace.
+    pub fn is_blank(&self, column: usize) -> bool {
+        self.cells
+            .get(column)
+            .map_or(true, |&(_, style)| style == Style::Plain)
+    }
+
+    /// Writes `s` into the row starting at `column`.
+    pub fn write(&mut self, column: usize, s: &str, style: Style) {
+        for (i, c) in s.chars().enumerate() {
+            self.put(column + i, c, style);
+        }
+    }
+
+    /// Moves every cell in the row `n` columns to the right.
+    pub fn shift(&mut self, n: usize) {
+        self.cells
+            .splice(0..0, std::iter::repeat((' ', Style::Plain)).take(n));
+    }
+
+    /// Pads the row with spaces until it is at least `len` columns wide.
+    pub fn pad(&mut self, len: usize) {
+        if self.cells.len() < len {
+            self.cells.resize(len, (' ', Style::Plain));
+        }
+    }
+
+    /// Removes unstyled whitespace from the end of the row.
+    pub fn trim(&mut self) {
+        while self
+            .cells
+            .last()
+            .is_some_and(|&(_, style)| style == Style::Plain)
+        {
+            self.cells.pop();
+        }
+    }
+
+    /// Returns the text of the row split into runs of identically styled characters.
+    pub fn segments(&self) -> Vec<(String, Style)> {
+        let mut segments: Vec<(String, Style)> = Vec::new();
+
+        for &(c, style) in &self.cells {
+            match segments.last_mut() {
+                Some((text, last)) if *last == style => text.push(c),
+                _ => segments.push((c.to_string(), style)),
+            }
+        }
+
+        segments
+    }
+}
+
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text: String = self.cells.iter().map(|&(c, _)| c).collect();
+        write!(f, "{}", text.trim_end())
+    }
+}
+
+/// Lays out the rows printed beneath a line annotated by `annotations`.
+///
+/// The first row always contains the underlines,
+/// with the following rows holding connectors and hanging labels.
+pub(crate) fn layout(annotations: &[Annotation], glyphs: &Glyphs) -> Vec<Row> {
+    let mut underlines = Row::default();
+
+    // Draw secondary underlines first so that primary underlines stay visible where they overlap
+    let (primary, secondary): (Vec<&Annotation>, Vec<_>) =
+        annotations.iter().partition(|a| a.primary);
+    for annotation in secondary.into_iter().chain(primary) {
+        for column in annotation.start..annotation.visible_end() {
+            underlines.put(column, annotation.underline(glyphs), annotation.style());
+        }
+    }
+
+    // Labels are placed from right to left,
+    // with labels sharing a column ordered from the shortest to the longest.
+    let mut labelled: Vec<(usize, &Annotation)> = annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, a)| a.label.is_some())
+        .collect();
+    labelled.sort_by_key(|&(i, a)| (std::cmp::Reverse(a.start), a.visible_end(), i));
+
+    let mut hanging = labelled.as_slice();
+    if let Some(&(_, rightmost)) = labelled.first() {
+        let shares_column = labelled
+            .get(1)
+            .is_some_and(|(_, next)| next.start == rightmost.start);
+        let overlapped = annotations
+            .iter()
+            .any(|a| a.visible_end() > rightmost.visible_end());
+
+        if !shares_column && !overlapped {
+            let label = rightmost.label.unwrap_or_default();
+            underlines.write(rightmost.visible_end() + 1, label, rightmost.style());
+            hanging = &labelled[1..];
+        }
+    }
+
+    let mut rows = vec![underlines];
+    if hanging.is_empty() {
+        return rows;
+    }
+
+    // Leave a row of connectors between the underlines and the first hanging label
+    rows.resize(hanging.len() + 2, Row::default());
+    for (depth, &(_, annotation)) in hanging.iter().enumerate() {
+        let label_row = depth + 2;
+
+        // Connectors pass behind the labels of annotations sharing their column
+        for row in &mut rows[1..label_row] {
+            if row.is_blank(annotation.start) {
+                row.put(annotation.start, glyphs.connector, annotation.style());
+            }
+        }
+
+        let label = annotation.label.unwrap_or_default();
+        rows[label_row].write(annotation.start, label, annotation.style());
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotation(start: usize, end: usize, label: Option<&str>, primary: bool) -> Annotation<'_> {
+        Annotation {
+            start,
+            end,
+            label,
+            primary,
+        }
+    }
+
+    fn render(annotations: &[Annotation]) -> Vec<String> {
+        layout(annotations, &Glyphs::UNICODE)
+            .iter()
+            .map(|row| row.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn unlabelled() {
+        let rows = render(&[annotation(2, 5, None, true)]);
+        assert_eq!(rows, ["  ‾‾‾"]);
+    }
+
+    #[test]
+    fn single_label_inline() {
+        let rows = render(&[annotation(2, 5, Some("here"), true)]);
+        assert_eq!(rows, ["  ‾‾‾ here"]);
+    }
+
+    #[test]
+    fn disjoint_labels_stack() {
+        let rows = render(&[
+            annotation(0, 3, Some("first"), true),
+            annotation(4, 7, Some("second"), false),
+            annotation(9, 12, Some("third"), false),
+        ]);
+
+        assert_eq!(
+            rows,
+            ["‾‾‾ ---  --- third", "│   │", "│   second", "first",]
+        );
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        let rows = render(&[
+            annotation(0, 6, Some("outer"), true),
+            annotation(4, 9, Some("overlap"), false),
+        ]);
+
+        assert_eq!(rows, ["‾‾‾‾‾‾--- overlap", "│", "outer"]);
+    }
+
+    #[test]
+    fn primary_drawn_over_secondary() {
+        let rows = render(&[annotation(4, 9, None, false), annotation(0, 6, None, true)]);
+
+        assert_eq!(rows, ["‾‾‾‾‾‾---"]);
+    }
+
+    #[test]
+    fn nested_ranges() {
+        let rows = render(&[
+            annotation(0, 10, Some("outer"), true),
+            annotation(3, 5, Some("inner"), false),
+        ]);
+
+        assert_eq!(rows, ["‾‾‾‾‾‾‾‾‾‾", "│  │", "│  inner", "outer"]);
+    }
+
+    #[test]
+    fn nested_ranges_sharing_start() {
+        let rows = render(&[
+            annotation(2, 10, Some("outer"), true),
+            annotation(2, 4, Some("inner"), false),
+        ]);
+
+        assert_eq!(rows, ["  ‾‾‾‾‾‾‾‾", "  │", "  inner", "  outer"]);
+    }
+
+    #[test]
+    fn identical_ranges() {
+        let rows = render(&[
+            annotation(1, 4, Some("first"), true),
+            annotation(1, 4, Some("second"), false),
+        ]);
+
+        assert_eq!(rows, [" ‾‾‾", " │", " first", " second"]);
+    }
+
+    #[test]
+    fn zero_width_same_column() {
+        let rows = render(&[
+            annotation(3, 3, Some("first"), true),
+            annotation(3, 3, Some("second"), false),
+        ]);
+
+        assert_eq!(rows, ["   ^", "   │", "   first", "   second"]);
+    }
+
+    #[test]
+    fn zero_width_inline() {
+        let rows = render(&[
+            annotation(0, 2, Some("range"), false),
+            annotation(5, 5, Some("point"), true),
+        ]);
+
+        assert_eq!(rows, ["--   ^ point", "│", "range"]);
+    }
+
+    #[test]
+    fn segments_split_on_style() {
+        let mut row = Row::default();
+        row.write(1, "ab", Style::Primary);
+        row.write(3, "c", Style::Secondary);
+
+        assert_eq!(
+            row.segments(),
+            [
+                (" ".to_owned(), Style::Plain),
+                ("ab".to_owned(), Style::Primary),
+                ("c".to_owned(), Style::Secondary),
+            ]
+        );
+    }
+}
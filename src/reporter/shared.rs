@@ -0,0 +1,296 @@
+//! A thread-safe handle to a [`TerminalReporter`].
+
+use std::{
+    fmt, io, mem,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError},
+};
+
+use anstream::stream::{AsLockedWrite, RawStream};
+
+use crate::{diagnostic::Diagnostic, lookup::Location, span::Span};
+
+use super::{LookupKey, ReportFlow, TerminalReporter};
+
+/// A cheaply cloneable handle to a [`TerminalReporter`] that can be shared between threads.
+///
+/// Every method locks the inner reporter for the duration of the call,
+/// so worker threads can report diagnostics concurrently without an async runtime.
+///
+/// Since diagnostics from different threads arrive in no particular order,
+/// [`emit_all`](SharedReporter::emit_all) first sorts them by their file name, span, level, message, and code,
+/// then applies the reporter's [`SortOrder`](super::SortOrder), keeping its output deterministic.
+/// Diagnostics printed by [`emit`](SharedReporter::emit) or by a [streaming](TerminalReporter::set_streaming) reporter
+/// are still printed in the order they arrive.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::SharedReporter;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let reporter = SharedReporter::new(TerminalReporter::default());
+/// let modules: Vec<_> = ["a", "b", "c"]
+///     .iter()
+///     .map(|name| reporter.register_file(format!("{name}.txt"), "let x = ;"))
+///     .collect();
+///
+/// std::thread::scope(|scope| {
+///     for &module in &modules {
+///         let reporter = reporter.clone();
+///         scope.spawn(move || {
+///             reporter.report(error!(Span::new(module, 8..9), "expected expression"));
+///         });
+///     }
+/// });
+///
+/// assert!(reporter.has_errors());
+/// reporter.emit_all()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SharedReporter<T: RawStream + AsLockedWrite> {
+    inner: Arc<Inner<T>>,
+}
+
+/// An operation on the reporter, waiting for it to be unlocked.
+type Deferred<T> = Box<dyn FnOnce(&mut TerminalReporter<T>) + Send>;
+
+struct Inner<T: RawStream + AsLockedWrite> {
+    reporter: Mutex<TerminalReporter<T>>,
+    deferred: Mutex<Vec<Deferred<T>>>,
+}
+
+impl<T: RawStream + AsLockedWrite + fmt::Debug> fmt::Debug for SharedReporter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedReporter")
+            .field("reporter", &self.inner.reporter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: RawStream + AsLockedWrite> Clone for SharedReporter<T> {
+    fn clone(&self) -> Self {
+        SharedReporter {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: RawStream + AsLockedWrite + Send> SharedReporter<T> {
+    /// Creates a handle sharing the given `reporter`.
+    pub fn new(reporter: TerminalReporter<T>) -> SharedReporter<T> {
+        SharedReporter {
+            inner: Arc::new(Inner {
+                reporter: Mutex::new(reporter),
+                deferred: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Locks the inner reporter, blocking until it is available.
+    ///
+    /// A reporter is still usable after a thread panics while holding the lock,
+    /// so that diagnostics reported before the panic are not lost.
+    /// Records that the `log` and `tracing` integrations received while the reporter was locked
+    /// are handled before the lock is returned.
+    pub fn lock(&self) -> MutexGuard<'_, TerminalReporter<T>> {
+        let mut reporter = self
+            .inner
+            .reporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.run_deferred(&mut reporter);
+
+        reporter
+    }
+
+    /// Runs `f` on the inner reporter if it isn't locked,
+    /// otherwise defers it until the reporter is next locked through any handle.
+    ///
+    /// Unlike [`lock`](SharedReporter::lock), this never blocks,
+    /// so it is safe to call while the current thread holds the lock,
+    /// e.g. from a logger invoked while printing a diagnostic.
+    // Only used by some of the optional integrations
+    #[allow(dead_code)]
+    pub(crate) fn lock_or_defer<F>(&self, f: F)
+    where
+        F: FnOnce(&mut TerminalReporter<T>) + Send + 'static,
+    {
+        let mut reporter = match self.inner.reporter.try_lock() {
+            Ok(reporter) => reporter,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                self.deferred().push(Box::new(f));
+                return;
+            }
+        };
+
+        self.run_deferred(&mut reporter);
+        f(&mut reporter);
+        self.run_deferred(&mut reporter);
+    }
+
+    fn deferred(&self) -> MutexGuard<'_, Vec<Deferred<T>>> {
+        self.inner
+            .deferred
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs the operations deferred while the reporter was locked, in the order they were deferred.
+    fn run_deferred(&self, reporter: &mut TerminalReporter<T>) {
+        loop {
+            // Deferred operations may defer further operations of their own
+            let deferred = mem::take(&mut *self.deferred());
+            if deferred.is_empty() {
+                return;
+            }
+
+            for f in deferred {
+                f(reporter);
+            }
+        }
+    }
+
+    /// Returns the inner reporter if this is the only handle to it,
+    /// otherwise returns the handle unchanged.
+    pub fn try_unwrap(self) -> Result<TerminalReporter<T>, SharedReporter<T>> {
+        drop(self.lock());
+        Arc::try_unwrap(self.inner)
+            .map(|inner| {
+                inner
+                    .reporter
+                    .into_inner()
+                    .unwrap_or_else(PoisonError::into_inner)
+            })
+            .map_err(|inner| SharedReporter { inner })
+    }
+
+    /// Inserts a file into the lookup table of the inner reporter.
+    ///
+    /// See [`TerminalReporter::register_file`] for more information.
+    pub fn register_file<N: ToString, F: ToString>(&self, name: N, contents: F) -> LookupKey {
+        self.lock().register_file(name, contents)
+    }
+
+    /// Adds the provided `diagnostic` to the inner reporter.
+    ///
+    /// See [`TerminalReporter::report`] for more information.
+    pub fn report(&self, diagnostic: Diagnostic) -> ReportFlow {
+        self.lock().report(diagnostic)
+    }
+
+    /// Adds the provided list of `diagnostics` to the inner reporter.
+    ///
+    /// See [`TerminalReporter::report_all`] for more information.
+    pub fn report_all(&self, diagnostics: Vec<Diagnostic>) -> ReportFlow {
+        self.lock().report_all(diagnostics)
+    }
+
+    /// Prints a diagnostic to the emitter of the inner reporter.
+    ///
+    /// See [`TerminalReporter::emit`] for more information.
+    pub fn emit(&self, diagnostic: Diagnostic) -> io::Result<()> {
+        self.lock().emit(diagnostic)
+    }
+
+    /// Prints all diagnostics reported to the inner reporter,
+    /// in an order that doesn't depend on which thread reported them first.
+    ///
+    /// See [`TerminalReporter::emit_all`] for more information.
+    pub fn emit_all(&self) -> io::Result<()> {
+        let mut reporter = self.lock();
+        reporter.sort_by_contents();
+        reporter.emit_all()
+    }
+
+    /// Returns `true` if any errors have been reported to the inner reporter.
+    ///
+    /// See [`TerminalReporter::has_errors`] for more information.
+    pub fn has_errors(&self) -> bool {
+        self.lock().has_errors()
+    }
+
+    /// Gets the line-column location of the span in its file.
+    ///
+    /// See [`TerminalReporter::location`] for more information.
+    pub fn location(&self, span: Span) -> Location {
+        self.lock().location(span)
+    }
+}
+
+impl<T: RawStream + AsLockedWrite + Send> From<TerminalReporter<T>> for SharedReporter<T> {
+    fn from(reporter: TerminalReporter<T>) -> Self {
+        SharedReporter::new(reporter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::reporter::SortOrder;
+
+    fn run(order: SortOrder) -> String {
+        let reporter = SharedReporter::new(TerminalReporter::new(Vec::new()).with_order(order));
+        let files: Vec<LookupKey> = ["a", "b"]
+            .iter()
+            .map(|name| reporter.register_file(format!("{name}.txt"), "let x = ;\n"))
+            .collect();
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let reporter = reporter.clone();
+                let files = &files;
+                scope.spawn(move || {
+                    for &file in files {
+                        let span = Span::new(file, 8..9);
+                        reporter
+                            .report(Diagnostic::spanned_error(span, format!("thread {thread}")));
+                        reporter.report(Diagnostic::spanned_warning(span, "expected expression"));
+                    }
+                    reporter.report(Diagnostic::error(format!("spanless {thread}")));
+                });
+            }
+        });
+
+        reporter.emit_all().unwrap();
+        let reporter = reporter.try_unwrap().unwrap();
+        assert_eq!(reporter.order(), order);
+
+        String::from_utf8(reporter.emitter.as_inner().clone()).unwrap()
+    }
+
+    #[test]
+    fn deterministic_output() {
+        for order in [
+            SortOrder::Reported,
+            SortOrder::SpanlessLast,
+            SortOrder::SpanlessInPlace,
+        ] {
+            let first = run(order);
+            for _ in 0..20 {
+                assert_eq!(run(order), first, "{order:?}");
+            }
+        }
+
+        let output = run(SortOrder::Reported);
+        let messages: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with(char::is_alphabetic))
+            .take(6)
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "error: spanless 0",
+                "error: spanless 1",
+                "error: spanless 2",
+                "error: spanless 3",
+                "error: thread 0",
+                "error: thread 1",
+            ]
+        );
+    }
+}
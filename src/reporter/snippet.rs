@@ -0,0 +1,580 @@
+//! Formatting of the source snippets printed beneath spanned diagnostics.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use colored::{Color, ColoredString, Colorize};
+
+use super::{
+    layout::{self, Annotation, Row, Style},
+    theme::Theme,
+};
+use crate::lookup::Lookup;
+
+/// Controls how many lines of source are printed around a spanned diagnostic.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::SnippetWindow;
+///
+/// // Show two lines of code around each span,
+/// // and print multiline spans of up to five lines in full.
+/// let reporter = TerminalReporter::default().with_window(SnippetWindow {
+///     leading_context: 2,
+///     trailing_context: 2,
+///     elide_after: 5,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetWindow {
+    /// The number of lines printed before the first spanned line.
+    pub leading_context: usize,
+    /// The number of lines printed after the last spanned line.
+    pub trailing_context: usize,
+    /// Multiline spans covering more than this many lines
+    /// only print their first and last lines, with `...` in between.
+    pub elide_after: usize,
+    /// The maximum width of a snippet in columns.
+    ///
+    /// Lines that would be wider are truncated to a window around the spans,
+    /// with `...` marking the cut off text on either side.
+    /// If `None`, the width of the terminal is used,
+    /// and lines are never truncated when not printing to a terminal.
+    pub width: Option<usize>,
+}
+
+impl Default for SnippetWindow {
+    fn default() -> Self {
+        SnippetWindow {
+            leading_context: 0,
+            trailing_context: 0,
+            elide_after: 1,
+            width: None,
+        }
+    }
+}
+
+/// A labelled range of characters to be annotated in a snippet.
+#[derive(Debug, Clone)]
+pub(crate) struct Label<'a> {
+    pub range: Range<usize>,
+    pub message: Option<&'a str>,
+    pub primary: bool,
+}
+
+/// A label spanning more than one line,
+/// drawn with a connecting line in the margin left of the source.
+struct Multiline<'a> {
+    first: usize,
+    last: usize,
+    start_col: usize,
+    end_col: usize,
+    message: Option<&'a str>,
+    style: Style,
+}
+
+/// The horizontal range of columns shown from each line of a snippet.
+#[derive(Debug, Clone, Copy)]
+struct Viewport {
+    left: usize,
+    width: usize,
+}
+
+impl Viewport {
+    const MARKER: &'static str = "...";
+
+    /// The number of columns taken up by the marker for cut off text on the left.
+    #[inline]
+    fn indent(&self) -> usize {
+        if self.left > 0 {
+            Self::MARKER.len()
+        } else {
+            0
+        }
+    }
+
+    /// Maps a column of the source into a column of the snippet,
+    /// clamping columns outside of the viewport to its edges.
+    fn column(&self, column: usize) -> usize {
+        let right = self.left.saturating_add(self.width);
+        column.clamp(self.left, right) - self.left + self.indent()
+    }
+
+    /// Cuts `line` down to the columns inside of the viewport.
+    fn text(&self, line: &str) -> String {
+        let length = line.chars().count();
+        let mut text: String = line.chars().skip(self.left).take(self.width).collect();
+
+        if self.left > 0 && length > 0 {
+            text.insert_str(0, Self::MARKER);
+        }
+        if length > self.left.saturating_add(self.width) {
+            text.push_str(Self::MARKER);
+        }
+
+        text
+    }
+}
+
+/// Returns the width of the terminal if the emitter is attached to one.
+pub(crate) fn terminal_width(terminal: bool) -> Option<usize> {
+    if !terminal {
+        return None;
+    }
+
+    terminal_size::terminal_size()
+        .or_else(|| terminal_size::terminal_size_of(std::io::stderr()))
+        .map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+enum Gutter {
+    Line(usize),
+    Blank,
+    Ellipsis,
+}
+
+/// Converts a character index within `line` into a display column.
+///
+/// Indices past the end of the line continue counting from its last column.
+fn column(line: &str, index: usize) -> usize {
+    let chars = line.char_indices().take_while(|&(i, _)| i < index).count();
+    chars + index.saturating_sub(line.len())
+}
+
+/// Formats a snippet of `lookup` annotated with each of the `labels`,
+/// returning the formatted snippet and the width of its line number gutter.
+///
+/// The location printed in the snippet's header is taken from the first label.
+/// If `file` is `None`, the header is omitted.
+pub(crate) fn pointer(
+    file: Option<&str>,
+    lookup: &Lookup,
+    labels: &[Label],
+    arrow_color: Color,
+    window: SnippetWindow,
+    theme: &Theme,
+) -> (String, usize) {
+    let glyphs = &theme.glyphs;
+    let mut single: BTreeMap<usize, Vec<Annotation>> = BTreeMap::new();
+    let mut multi = Vec::new();
+    let mut shown = BTreeSet::new();
+
+    let last_line = labels
+        .iter()
+        .map(|label| lookup.lines(label.range.clone()).end - 1)
+        .max()
+        .unwrap_or_default();
+    // A trailing line break leaves an empty line at the end of the file,
+    // which isn't worth printing as context
+    let mut final_line = lookup.line_count() - 1;
+    if final_line > last_line && lookup.line(final_line).is_empty() {
+        final_line -= 1;
+    }
+
+    for label in labels {
+        let lines = lookup.lines(label.range.clone());
+        let first = lines.start;
+        let last = lines.end - 1;
+        let style = if label.primary {
+            Style::Primary
+        } else {
+            Style::Secondary
+        };
+
+        let start_col = column(
+            lookup.line(first),
+            lookup.col_from_line(first, label.range.start),
+        );
+        let end_col = column(
+            lookup.line(last),
+            lookup.col_from_line(last, label.range.end),
+        );
+
+        if first == last {
+            single.entry(first).or_default().push(Annotation {
+                start: start_col,
+                end: end_col,
+                label: label.message,
+                primary: label.primary,
+            });
+        } else {
+            multi.push(Multiline {
+                first,
+                last,
+                start_col,
+                end_col,
+                message: label.message,
+                style,
+            });
+        }
+
+        if lines.len() > window.elide_after {
+            shown.insert(first);
+            shown.insert(last);
+        } else {
+            shown.extend(lines);
+        }
+
+        shown.extend(first.saturating_sub(window.leading_context)..first);
+        shown.extend(last + 1..=(last + window.trailing_context).min(final_line));
+    }
+
+    // Eliding a single line saves no space, so print it instead
+    let gaps: Vec<usize> = shown
+        .iter()
+        .zip(shown.iter().skip(1))
+        .filter(|&(a, b)| b - a == 2)
+        .map(|(a, _)| a + 1)
+        .collect();
+    shown.extend(gaps);
+
+    let elided = shown
+        .iter()
+        .zip(shown.iter().skip(1))
+        .any(|(a, b)| b - a > 1);
+
+    let mut offset = (shown.last().copied().unwrap_or_default() + 1).ilog10() as usize + 2;
+    if elided {
+        // Leave enough room for the `...` in place of the line number
+        offset = offset.max(4);
+    }
+
+    let margin = multi.len() * 2;
+
+    let viewport = window
+        .width
+        .map(|width| {
+            let longest = shown
+                .iter()
+                .map(|&n| lookup.line(n).trim_end().chars().count())
+                .max()
+                .unwrap_or_default();
+            (width.saturating_sub(offset + 2 + margin), longest)
+        })
+        .filter(|&(available, longest)| longest > available)
+        .map(|(available, longest)| {
+            // Leave room for the markers on either side of the window
+            let width = available.saturating_sub(Viewport::MARKER.len() * 2).max(1);
+
+            // Keep every annotated column in view when possible
+            let (lo, hi) = single
+                .values()
+                .flatten()
+                .map(|a| (a.start, a.end.max(a.start + 1)))
+                .chain(multi.iter().flat_map(|m| {
+                    [
+                        (m.start_col, m.start_col + 1),
+                        (m.end_col.saturating_sub(1), m.end_col),
+                    ]
+                }))
+                .fold((usize::MAX, 0), |(lo, hi), (start, end)| {
+                    (lo.min(start), hi.max(end))
+                });
+
+            let left = if hi <= width {
+                0
+            } else {
+                let slack = width.saturating_sub(hi - lo);
+                lo.saturating_sub(slack / 2)
+                    .min(longest.saturating_sub(width))
+            };
+
+            Viewport { left, width }
+        })
+        .unwrap_or(Viewport {
+            left: 0,
+            width: usize::MAX,
+        });
+
+    for annotation in single.values_mut().flatten() {
+        annotation.start = viewport.column(annotation.start);
+        annotation.end = viewport.column(annotation.end);
+    }
+    for m in &mut multi {
+        m.start_col = viewport.column(m.start_col);
+        m.end_col = viewport.column(m.end_col);
+    }
+
+    let mut open = vec![false; multi.len()];
+    let margin_row = |open: &[bool]| {
+        let mut row = Row::default();
+        for (slot, m) in multi.iter().enumerate().filter(|&(slot, _)| open[slot]) {
+            row.put(slot * 2, glyphs.connector, m.style);
+        }
+        row
+    };
+
+    let mut rows: Vec<(Gutter, Row, String)> = Vec::new();
+    let mut previous = None;
+
+    for &n in &shown {
+        if previous.is_some_and(|previous| n > previous + 1) {
+            rows.push((Gutter::Ellipsis, margin_row(&open), String::new()));
+        }
+        previous = Some(n);
+
+        let line = lookup.line(n).trim_end();
+        let line_end = viewport.column(line.chars().count());
+        let mut row = margin_row(&open);
+        row.pad(margin);
+        rows.push((Gutter::Line(n), row, viewport.text(line)));
+
+        for (slot, m) in multi.iter().enumerate().filter(|(_, m)| m.first == n) {
+            let mut row = margin_row(&open);
+            row.put(slot * 2, glyphs.multiline_start, m.style);
+            for col in slot * 2 + 1..margin {
+                row.put(col, glyphs.horizontal, m.style);
+            }
+            for col in 0..m.start_col {
+                row.put(margin + col, glyphs.multiline_lead, m.style);
+            }
+            for col in m.start_col..line_end.max(m.start_col + 1) {
+                row.put(margin + col, glyphs.multiline_underline, m.style);
+            }
+
+            open[slot] = true;
+            rows.push((Gutter::Blank, row, String::new()));
+        }
+
+        if let Some(annotations) = single.get(&n) {
+            for mut row in layout::layout(annotations, glyphs) {
+                row.shift(margin);
+                for (slot, m) in multi.iter().enumerate().filter(|&(slot, _)| open[slot]) {
+                    row.put(slot * 2, glyphs.connector, m.style);
+                }
+                rows.push((Gutter::Blank, row, String::new()));
+            }
+        }
+
+        for (slot, m) in multi.iter().enumerate().rev().filter(|(_, m)| m.last == n) {
+            let mut row = margin_row(&open);
+            row.put(slot * 2, glyphs.multiline_end, m.style);
+            for col in slot * 2 + 1..margin + m.end_col {
+                row.put(col, glyphs.horizontal, m.style);
+            }
+            if let Some(message) = m.message {
+                row.write(margin + m.end_col + 1, message, m.style);
+            }
+
+            open[slot] = false;
+            rows.push((Gutter::Blank, row, String::new()));
+        }
+    }
+
+    let (line_n, col_n) = labels
+        .first()
+        .map(|label| lookup.line_col(label.range.start))
+        .unwrap_or_default();
+
+    let cap = theme.gutter.paint(glyphs.gutter.to_string());
+    let mut snippet = match file {
+        Some(file) => format!(
+            "{arrow:>arr_space$} [{name}:{line}:{col}]\n{cap:>width$}",
+            arrow = theme.gutter.paint(glyphs.arrow),
+            arr_space = offset + 2,
+            name = theme.filename.paint(file),
+            line = line_n + 1,
+            col = col_n + 1,
+            width = offset + 1,
+        ),
+        None => format!("{cap:>width$}", width = offset + 1),
+    };
+
+    for (gutter, mut row, text) in rows {
+        if text.is_empty() {
+            row.trim();
+        }
+
+        let gutter = match gutter {
+            Gutter::Line(n) => theme
+                .gutter
+                .paint(format!("{:<offset$}{}", n + 1, glyphs.gutter)),
+            Gutter::Blank => format!("{cap:>width$}", width = offset + 1).into(),
+            Gutter::Ellipsis => {
+                theme
+                    .gutter
+                    .paint(format!("{:<width$}", "...", width = offset + 1))
+            }
+        };
+
+        let body = format!("{}{text}", paint(&row, arrow_color, theme.secondary));
+        if body.is_empty() {
+            snippet.push_str(&format!("\n{gutter}"));
+        } else {
+            snippet.push_str(&format!("\n{gutter} {body}"));
+        }
+    }
+
+    (snippet.trim_end().to_owned(), offset)
+}
+
+/// Colors each segment of `row` according to its style.
+fn paint(row: &Row, primary: Color, secondary: Color) -> String {
+    row.segments()
+        .into_iter()
+        .map(|(text, style)| -> ColoredString {
+            match style {
+                Style::Plain => text.into(),
+                Style::Primary => text.color(primary).bold(),
+                Style::Secondary => text.color(secondary).bold(),
+            }
+        })
+        .map(|text| text.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, range: Range<usize>) -> String {
+        render_window(source, range, SnippetWindow::default())
+    }
+
+    fn render_window(source: &str, range: Range<usize>, window: SnippetWindow) -> String {
+        colored::control::set_override(false);
+
+        let lookup = Lookup::new(source.to_owned());
+        let label = Label {
+            range,
+            message: None,
+            primary: true,
+        };
+
+        pointer(
+            Some("test.txt"),
+            &lookup,
+            &[label],
+            Color::Red,
+            window,
+            &Theme::UNICODE,
+        )
+        .0
+    }
+
+    #[test]
+    fn insertion_point() {
+        assert_eq!(
+            render("foo bar", 3..3),
+            " ——> [test.txt:1:4]\n  ┃\n1 ┃ foo bar\n  ┃    ^"
+        );
+    }
+
+    #[test]
+    fn eof_without_trailing_newline() {
+        let lookup = Lookup::new("foo\nbar".to_owned());
+        let eof = lookup.eof();
+
+        assert_eq!(
+            render("foo\nbar", eof..eof),
+            " ——> [test.txt:2:4]\n  ┃\n2 ┃ bar\n  ┃    ^"
+        );
+    }
+
+    #[test]
+    fn eof_with_trailing_newline() {
+        let lookup = Lookup::new("foo\nbar\n".to_owned());
+        let eof = lookup.eof();
+
+        assert_eq!(
+            render("foo\nbar\n", eof..eof),
+            " ——> [test.txt:2:4]\n  ┃\n2 ┃ bar\n  ┃    ^"
+        );
+    }
+
+    #[test]
+    fn empty_file() {
+        assert_eq!(render("", 0..0), " ——> [test.txt:1:1]\n  ┃\n1 ┃\n  ┃ ^");
+    }
+
+    #[test]
+    fn empty_file_with_newline() {
+        let lookup = Lookup::new("\n".to_owned());
+        let eof = lookup.eof();
+
+        assert_eq!(
+            render("\n", eof..eof),
+            " ——> [test.txt:1:1]\n  ┃\n1 ┃\n  ┃ ^"
+        );
+    }
+
+    #[test]
+    fn long_line_windowed() {
+        let source = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let window = SnippetWindow {
+            width: Some(30),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_window(&source, 100..106, window),
+            " ——> [test.txt:1:101]\n  ┃\n1 ┃ ...aaaaaaaneedlebbbbbbb...\n  ┃           ‾‾‾‾‾‾"
+        );
+    }
+
+    #[test]
+    fn long_line_start() {
+        let source = "a".repeat(100);
+        let window = SnippetWindow {
+            width: Some(20),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_window(&source, 0..2, window),
+            " ——> [test.txt:1:1]\n  ┃\n1 ┃ aaaaaaaaaa...\n  ┃ ‾‾"
+        );
+    }
+
+    #[test]
+    fn short_line_unwindowed() {
+        let window = SnippetWindow {
+            width: Some(20),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render_window("foo bar", 4..7, window),
+            " ——> [test.txt:1:5]\n  ┃\n1 ┃ foo bar\n  ┃     ‾‾‾"
+        );
+    }
+
+    #[test]
+    fn ascii_glyphs() {
+        colored::control::set_override(false);
+
+        let lookup = Lookup::new("foo(bar)\n{\n}".to_owned());
+        let labels = [
+            Label {
+                range: 9..12,
+                message: None,
+                primary: true,
+            },
+            Label {
+                range: 4..7,
+                message: Some("argument"),
+                primary: false,
+            },
+        ];
+
+        let (snippet, _) = pointer(
+            Some("test.txt"),
+            &lookup,
+            &labels,
+            Color::Red,
+            SnippetWindow::default(),
+            &Theme::ASCII,
+        );
+
+        assert!(snippet.is_ascii());
+        assert_eq!(
+            snippet,
+            " --> [test.txt:2:1]\n  |\n1 |   foo(bar)\n  |       --- argument\n2 |   {\n  | /_^\n3 | | }\n  | \\__"
+        );
+    }
+}
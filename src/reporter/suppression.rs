@@ -0,0 +1,346 @@
+//! Suppression of lints through comments in registered source files.
+
+use std::ops::Range;
+
+use crate::{
+    diagnostic::{Diagnostic, LintLevel, LintRegistry, Note},
+    lookup::Lookup,
+    span::Span,
+};
+
+use super::LookupKey;
+
+/// The directive that begins a suppression comment.
+const DIRECTIVE: &str = "nurse-allow(";
+
+/// The comment syntax used to suppress lints from within registered source files.
+///
+/// A suppression comment consists of the host language's comment prefix,
+/// followed by `nurse-allow` and a comma-separated list of lint names:
+///
+/// ```text
+/// ; nurse-allow(unused_label)
+/// loop:
+///     jmp end ; nurse-allow(unreachable, unused_label)
+/// ```
+///
+/// A comment following code suppresses the listed lints on its own line,
+/// while a comment on a line by itself suppresses them on the next line that is not a suppression comment.
+/// A diagnostic is suppressed when its [`lint`](Diagnostic::lint) is listed
+/// and its span starts on a suppressed line.
+///
+/// Comments are found by searching for the prefix,
+/// so a prefix appearing inside a string literal is treated as a comment.
+///
+/// Lints set to [`Forbid`](LintLevel::Forbid) in the [registry](Suppressions::set_lints) can't be suppressed.
+/// A suppression comment listing one is reported as an error when it matches a diagnostic,
+/// and the diagnostic is reported as usual.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::{Lint, LintLevel, LintRegistry, Suppressions};
+///
+/// const UNUSED_LABEL: Lint = Lint::new("unused_label", LintLevel::Warn);
+///
+/// let mut lints = LintRegistry::new();
+/// lints.register(UNUSED_LABEL);
+///
+/// let mut reporter = TerminalReporter::default()
+///     .with_suppressions(Suppressions::new(";").with_warn_unused(true));
+///
+/// let key = reporter.register_file("main.asm", "; nurse-allow(unused_label)\nloop:\n");
+/// let span = Span::new(key, 28..32);
+///
+/// reporter.report(lints.lint(&UNUSED_LABEL, "unused label `loop`").unwrap().with_span(Some(span)));
+/// assert!(reporter.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suppressions {
+    comment: String,
+    warn_unused: bool,
+    lints: LintRegistry,
+}
+
+impl Suppressions {
+    /// Creates a new suppression syntax using the given line comment prefix, such as `//` or `;`.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if `comment` is empty.
+    pub fn new<S: Into<String>>(comment: S) -> Suppressions {
+        let comment = comment.into();
+        assert!(!comment.is_empty(), "comment prefix should not be empty");
+
+        Suppressions {
+            comment,
+            warn_unused: false,
+            lints: LintRegistry::new(),
+        }
+    }
+
+    /// Gets the comment prefix that suppression comments start with.
+    #[inline]
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Sets whether suppressions that never matched a diagnostic produce a warning
+    /// when [`report_unused_suppressions`](crate::TerminalReporter::report_unused_suppressions) is called.
+    #[inline]
+    pub fn set_warn_unused(&mut self, warn_unused: bool) {
+        self.warn_unused = warn_unused;
+    }
+
+    /// Returns the suppression syntax with warnings for unused suppressions enabled or disabled.
+    #[inline]
+    pub fn with_warn_unused(mut self, warn_unused: bool) -> Self {
+        self.warn_unused = warn_unused;
+        self
+    }
+
+    /// Checks whether suppressions that never matched a diagnostic produce a warning.
+    #[inline]
+    pub fn warn_unused(&self) -> bool {
+        self.warn_unused
+    }
+
+    /// Sets the registry used to find [forbidden](LintLevel::Forbid) lints, which can't be suppressed.
+    ///
+    /// The registry is copied, so later changes to its levels must be set again.
+    #[inline]
+    pub fn set_lints(&mut self, lints: LintRegistry) {
+        self.lints = lints;
+    }
+
+    /// Returns the suppression syntax using `lints` to find [forbidden](LintLevel::Forbid) lints.
+    #[inline]
+    pub fn with_lints(mut self, lints: LintRegistry) -> Self {
+        self.lints = lints;
+        self
+    }
+
+    /// Gets the registry used to find forbidden lints.
+    #[inline]
+    pub fn lints(&self) -> &LintRegistry {
+        &self.lints
+    }
+
+    /// Checks whether the lint called `name` is registered and set to [`Forbid`](LintLevel::Forbid).
+    pub(crate) fn is_forbidden(&self, name: &str) -> bool {
+        self.lints
+            .get(name)
+            .is_some_and(|lint| self.lints.level(&lint) == LintLevel::Forbid)
+    }
+
+    /// Finds every lint suppressed by comments in `lookup`.
+    pub(crate) fn parse(&self, lookup: &Lookup) -> Vec<Allow> {
+        let mut allows = Vec::new();
+        // Suppressions from comments on their own line, waiting for the line they apply to
+        let mut pending = 0;
+
+        for line in 0..lookup.line_count() {
+            let text = lookup.line(line).trim_end_matches(['\n', '\r']);
+            let head = lookup.line_start(line);
+
+            let directive = text
+                .match_indices(self.comment.as_str())
+                .find_map(|(i, _)| {
+                    let rest = text[i + self.comment.len()..].trim_start();
+                    let list = rest.strip_prefix(DIRECTIVE)?;
+                    let end = list.find(')')?;
+
+                    Some((i, &list[..end]))
+                });
+
+            let standalone = match directive {
+                Some((i, list)) => {
+                    for lint in list.split(',') {
+                        let trimmed = lint.trim();
+                        if trimmed.is_empty() {
+                            continue;
+                        }
+
+                        // The offset of the lint name within the line
+                        let start = trimmed.as_ptr() as usize - text.as_ptr() as usize;
+                        allows.push(Allow {
+                            line,
+                            lint: trimmed.to_owned(),
+                            range: head + start..head + start + trimmed.len(),
+                            used: false,
+                        });
+                    }
+
+                    text[..i].trim().is_empty()
+                }
+                None => false,
+            };
+
+            if !standalone {
+                for allow in &mut allows[pending..] {
+                    allow.line = line;
+                }
+                pending = allows.len();
+            }
+        }
+
+        // Comments at the end of the file apply to a line that doesn't exist
+        for allow in &mut allows[pending..] {
+            allow.line = lookup.line_count();
+        }
+
+        allows
+    }
+}
+
+/// A lint suppressed on a single line by a suppression comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Allow {
+    line: usize,
+    lint: String,
+    /// The location of the lint's name in the comment
+    range: Range<usize>,
+    used: bool,
+}
+
+impl Allow {
+    /// Gets the span of the lint's name in the comment.
+    fn span(&self, key: LookupKey) -> Span {
+        Span {
+            lookup: key,
+            start: self.range.start,
+            end: self.range.end,
+        }
+    }
+}
+
+/// Checks whether `diagnostic` is suppressed by any of `allows` in the file `key`,
+/// marking the matching suppressions as used.
+///
+/// Diagnostics of `forbidden` lints are never suppressed.
+/// Instead, an error is returned for every matching suppression that hasn't been reported yet.
+pub(crate) fn suppress(
+    allows: &mut [Allow],
+    lookup: &Lookup,
+    key: LookupKey,
+    diagnostic: &Diagnostic,
+    forbidden: bool,
+) -> Result<bool, Vec<Diagnostic>> {
+    let Some(lint) = diagnostic.lint else {
+        return Ok(false);
+    };
+    let Some(span) = diagnostic.span else {
+        return Ok(false);
+    };
+
+    let line = lookup.line_n(span.start);
+    let mut suppressed = false;
+    let mut errors = Vec::new();
+    for allow in allows
+        .iter_mut()
+        .filter(|allow| allow.line == line && allow.lint == lint)
+    {
+        if forbidden && !allow.used {
+            errors.push(
+                Diagnostic::spanned_error(
+                    allow.span(key),
+                    format!("suppression of `{lint}` incompatible with forbid"),
+                )
+                .with_notes([
+                    Note::spanned(span, "the forbidden lint is reported here"),
+                    Note::from(format!(
+                        "`{lint}` is set to `forbid`, so it can't be suppressed"
+                    )),
+                ]),
+            );
+        }
+
+        allow.used = true;
+        suppressed = true;
+    }
+
+    if forbidden {
+        Err(errors)
+    } else {
+        Ok(suppressed)
+    }
+}
+
+/// Creates a warning for every suppression in `allows` that never matched a diagnostic,
+/// marking them as used so that they are only warned about once.
+pub(crate) fn unused(allows: &mut [Allow], key: LookupKey) -> Vec<Diagnostic> {
+    let mut warnings = Vec::new();
+
+    for allow in allows.iter_mut().filter(|allow| !allow.used) {
+        allow.used = true;
+
+        warnings.push(
+            Diagnostic::spanned_warning(
+                allow.span(key),
+                format!("unused suppression of `{}`", allow.lint),
+            )
+            .with_note(format!(
+                "no `{}` diagnostic was reported on the suppressed line",
+                allow.lint
+            )),
+        );
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Vec<(usize, String)> {
+        Suppressions::new(";")
+            .parse(&Lookup::new(source.to_owned()))
+            .into_iter()
+            .map(|allow| (allow.line, allow.lint))
+            .collect()
+    }
+
+    #[test]
+    fn trailing_comment() {
+        let allows = parse("loop:\n  jmp end ; nurse-allow(unreachable, unused_label)\n");
+        assert_eq!(
+            allows,
+            [
+                (1, "unreachable".to_owned()),
+                (1, "unused_label".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn standalone_comment() {
+        let allows = parse("; nurse-allow(a)\n  ;nurse-allow(b)\nloop:\n");
+        assert_eq!(allows, [(2, "a".to_owned()), (2, "b".to_owned())]);
+    }
+
+    #[test]
+    fn ordinary_comments() {
+        let allows = parse("; just a comment\nloop: ; nurse-allow(broken\n");
+        assert!(allows.is_empty());
+    }
+
+    #[test]
+    fn comment_at_eof() {
+        let allows = parse("loop:\n; nurse-allow(a)");
+        assert_eq!(allows, [(2, "a".to_owned())]);
+    }
+
+    #[test]
+    fn lint_range() {
+        let source = "x ; nurse-allow( a ,bc)";
+        let allows = Suppressions::new(";").parse(&Lookup::new(source.to_owned()));
+
+        let names: Vec<&str> = allows
+            .iter()
+            .map(|allow| &source[allow.range.clone()])
+            .collect();
+        assert_eq!(names, ["a", "bc"]);
+    }
+}
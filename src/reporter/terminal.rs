@@ -2,22 +2,34 @@ use anstream::{
     stream::{AsLockedWrite, RawStream},
     AutoStream,
 };
-use colored::{Color, Colorize};
-use slotmap::SlotMap;
+use colored::Colorize;
+use slotmap::{SecondaryMap, SlotMap};
 #[cfg(not(feature = "smol"))]
 use std::io::{self, Write};
+use std::{
+    collections::HashSet,
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
 
 #[cfg(feature = "smol")]
 use smol::{io::AsyncWriteExt, lock::Mutex, Unblock};
 
 use crate::{
-    diagnostic::{Diagnostic, LevelFilter},
-    lookup::{Location, Lookup},
+    diagnostic::{Diagnostic, Level, LevelFilter, LevelPolicy},
+    lookup::{Location, LocationError, Lookup},
     span::Span,
     Note,
 };
 
-use super::LookupKey;
+#[cfg(not(feature = "smol"))]
+use super::buffer::DiagnosticBuffer;
+use super::{
+    snippet::{self, Label, SnippetWindow},
+    suppression::{self, Allow, Suppressions},
+    theme::Theme,
+    LookupKey,
+};
 
 #[cfg(not(feature = "smol"))]
 type Emitter<T> = AutoStream<T>;
@@ -32,15 +44,306 @@ fn new_emitter<T: RawStream>(emitter: T) -> Emitter<T> {
     return Unblock::new(AutoStream::auto(emitter));
 }
 
+type Lookups = SlotMap<LookupKey, (String, Lookup)>;
+type Allows = SecondaryMap<LookupKey, Vec<Allow>>;
+
+/// Signals whether the caller should keep reporting diagnostics,
+/// returned by [`TerminalReporter::report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReportFlow {
+    /// The diagnostic was reported and the caller can continue.
+    Continue,
+    /// The [maximum error count](TerminalReporter::set_max_errors) has been reached,
+    /// and the caller should stop as soon as possible.
+    Abort,
+}
+
+impl ReportFlow {
+    /// Returns `true` if the caller should stop reporting diagnostics.
+    #[inline]
+    pub fn is_abort(&self) -> bool {
+        *self == ReportFlow::Abort
+    }
+}
+
+/// Tracks reported errors against the reporter's maximum error count.
+#[derive(Debug, Clone, Default)]
+struct ErrorCount {
+    reported: usize,
+    dropped: usize,
+    announced: bool,
+}
+
+impl ErrorCount {
+    /// Counts a reported error, returning whether it should be stored.
+    fn count(&mut self, max: Option<usize>) -> bool {
+        self.reported += 1;
+
+        match max {
+            Some(max) if self.reported > max => {
+                self.dropped += 1;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    fn signal(&self, max: Option<usize>) -> ReportFlow {
+        match max {
+            Some(max) if self.reported >= max => ReportFlow::Abort,
+            _ => ReportFlow::Continue,
+        }
+    }
+
+    /// Creates the diagnostic announcing that the maximum was reached,
+    /// returning `None` if it hasn't been reached or was already announced.
+    fn announce(&mut self, max: Option<usize>) -> Option<Diagnostic> {
+        if self.announced || self.signal(max) == ReportFlow::Continue {
+            return None;
+        }
+        self.announced = true;
+
+        let mut diagnostic = Diagnostic::error("too many errors, stopping");
+        if self.dropped > 0 {
+            diagnostic.set_note(format!(
+                "{} further error{} not shown",
+                self.dropped,
+                if self.dropped == 1 { " was" } else { "s were" }
+            ));
+        }
+
+        Some(diagnostic)
+    }
+}
+
+/// The order in which [`emit_all`](TerminalReporter::emit_all) prints reported diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortOrder {
+    /// Diagnostics are printed in the order they were reported.
+    #[default]
+    Reported,
+    /// Diagnostics are sorted by the order their files were registered in,
+    /// then by the start of their span.
+    /// Diagnostics without a span are printed after every spanned diagnostic.
+    SpanlessLast,
+    /// Diagnostics are sorted by the order their files were registered in,
+    /// then by the start of their span.
+    /// Diagnostics without a span keep their position in the reported order.
+    SpanlessInPlace,
+}
+
+impl SortOrder {
+    /// Sorts `diagnostics` in this order.
+    ///
+    /// Sorting is stable, so diagnostics at the same position stay in the order they were reported.
+    fn sort(&self, diagnostics: Vec<Diagnostic>, lookups: &Lookups) -> Vec<Diagnostic> {
+        if *self == SortOrder::Reported {
+            return diagnostics;
+        }
+
+        // Files are never removed from a reporter, so slots are iterated in registration order
+        let rank: SecondaryMap<LookupKey, usize> = lookups
+            .keys()
+            .enumerate()
+            .map(|(rank, key)| (key, rank))
+            .collect();
+
+        let len = diagnostics.len();
+        let (mut spanned, spanless): (Vec<_>, Vec<_>) = diagnostics
+            .into_iter()
+            .enumerate()
+            .partition(|(_, diagnostic)| diagnostic.span.is_some());
+        spanned.sort_by_key(|(_, diagnostic)| {
+            diagnostic
+                .span
+                .map(|span| (rank.get(span.lookup).copied(), span.start))
+        });
+
+        match self {
+            SortOrder::SpanlessInPlace => {
+                let mut spanned = spanned.into_iter();
+                let mut spanless = spanless.into_iter().peekable();
+
+                // Fill the slots not taken by span-less diagnostics with the sorted diagnostics
+                (0..len)
+                    .filter_map(|i| match spanless.next_if(|&(j, _)| i == j) {
+                        Some((_, diagnostic)) => Some(diagnostic),
+                        None => spanned.next().map(|(_, diagnostic)| diagnostic),
+                    })
+                    .collect()
+            }
+            _ => spanned
+                .into_iter()
+                .chain(spanless)
+                .map(|(_, diagnostic)| diagnostic)
+                .collect(),
+        }
+    }
+}
+
+/// Splits `diagnostics` into groups sharing the file of their span,
+/// ordered by the first diagnostic of each group.
+/// Diagnostics without a span are placed in a final group without a file.
+fn group(diagnostics: Vec<Diagnostic>) -> Vec<(Option<LookupKey>, Vec<Diagnostic>)> {
+    let mut groups: Vec<(Option<LookupKey>, Vec<Diagnostic>)> = Vec::new();
+    let mut spanless = Vec::new();
+
+    for diagnostic in diagnostics {
+        let Some(key) = diagnostic.span.map(|span| span.lookup) else {
+            spanless.push(diagnostic);
+            continue;
+        };
+
+        match groups.iter_mut().find(|(group, _)| *group == Some(key)) {
+            Some((_, group)) => group.push(diagnostic),
+            None => groups.push((Some(key), vec![diagnostic])),
+        }
+    }
+
+    if !spanless.is_empty() {
+        groups.push((None, spanless));
+    }
+
+    groups
+}
+
+/// Formats the header printed before a group of diagnostics in the file `name`,
+/// e.g. `── src/main.asm (3 errors, 1 warning) ──`.
+fn format_header(name: &str, diagnostics: &[Diagnostic], theme: &Theme) -> String {
+    let count = |level: Level, title: &str| {
+        let n = diagnostics.iter().filter(|d| d.level == level).count();
+        match n {
+            0 => None,
+            1 => Some(format!("1 {title}")),
+            n => Some(format!("{n} {title}s")),
+        }
+    };
+
+    let counts: Vec<String> = [count(Level::Error, "error"), count(Level::Warn, "warning")]
+        .into_iter()
+        .flatten()
+        .collect();
+    let rule = theme
+        .gutter
+        .paint(theme.glyphs.horizontal.to_string().repeat(2));
+    let name = theme.filename.paint(name);
+
+    if counts.is_empty() {
+        format!("{rule} {name} {rule}\n\n")
+    } else {
+        format!("{rule} {name} ({}) {rule}\n\n", counts.join(", "))
+    }
+}
+
+/// Formats a diagnostic along with the snippets of its span and spanned notes.
+///
+/// Snippets in the file of the current `group` are printed without their `[file:line:col]` header,
+/// and are only truncated to the width of the terminal if the emitter is a `terminal`.
+fn format_diagnostic(
+    lookups: &Lookups,
+    diagnostic: &Diagnostic,
+    group: Option<LookupKey>,
+    window: SnippetWindow,
+    terminal: bool,
+    theme: &Theme,
+) -> String {
+    let window = SnippetWindow {
+        width: window.width.or_else(|| snippet::terminal_width(terminal)),
+        ..window
+    };
+    let mut note_offset = diagnostic.title().len() + 1;
+    let color = theme.level(diagnostic.level);
+    let mut formatted = format!("{}\n", diagnostic.format_message(color));
+
+    let get = |key: LookupKey| {
+        let (file, lookup) = lookups
+            .get(key)
+            .expect("span should refer to an already registered file");
+
+        (Some(file.as_str()).filter(|_| group != Some(key)), lookup)
+    };
+
+    // Spanned notes in the same file are drawn as labels in the main snippet
+    let labelled = |note: &&Note| {
+        note.span
+            .zip(diagnostic.span)
+            .is_some_and(|(note, primary)| note.lookup() == primary.lookup())
+    };
+
+    if let Some(span) = diagnostic.span {
+        let labels: Vec<Label> = std::iter::once(Label {
+            range: span.range(),
+            message: None,
+            primary: true,
+        })
+        .chain(diagnostic.notes.iter().filter(labelled).map(|note| Label {
+            range: note.span.map(|span| span.range()).unwrap_or_default(),
+            message: Some(&note.value),
+            primary: false,
+        }))
+        .collect();
+
+        let (file, lookup) = get(span.lookup());
+        let (pointer, offset) = snippet::pointer(file, lookup, &labels, color, window, theme);
+        note_offset = offset + 1;
+        formatted.push_str(&format!("{pointer}\n"));
+    }
+
+    for note in diagnostic.notes.iter().filter(|note| !labelled(note)) {
+        match note.span {
+            Some(span) => {
+                let (file, lookup) = get(span.lookup());
+                let label = Label {
+                    range: span.range(),
+                    message: Some(&note.value),
+                    primary: false,
+                };
+
+                let (pointer, _) = snippet::pointer(file, lookup, &[label], color, window, theme);
+                formatted.push_str(&format!("{pointer}\n"));
+            }
+            None => formatted.push_str(&format!(
+                "{:>note_offset$} {}: {}\n",
+                theme.gutter.paint(theme.glyphs.note.to_string()),
+                note.title().bold(),
+                note.value
+            )),
+        }
+    }
+
+    if diagnostic.span.is_some() || !diagnostic.notes.is_empty() {
+        formatted.push('\n');
+    }
+
+    formatted
+}
+
 /// A reporter that formats and displays reported diagnostics
 /// to the terminal.
 #[cfg(not(feature = "smol"))]
 #[derive(Debug)]
 pub struct TerminalReporter<T: RawStream + AsLockedWrite> {
-    diagnostics: Vec<Diagnostic>,
-    lookups: SlotMap<LookupKey, (String, Lookup)>,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    pub(crate) lookups: Lookups,
     filter: LevelFilter,
-    emitter: Emitter<T>,
+    policy: LevelPolicy,
+    window: SnippetWindow,
+    terminal: bool,
+    theme: Theme,
+    summarize: bool,
+    dedup: bool,
+    seen: HashSet<Diagnostic>,
+    suppressions: Option<Suppressions>,
+    allows: Allows,
+    max_errors: Option<usize>,
+    errors: ErrorCount,
+    order: SortOrder,
+    grouped: bool,
+    streaming: bool,
+    streamed_errors: AtomicBool,
+    stream_error: Option<io::Error>,
+    emitted: [AtomicUsize; 4],
+    pub(crate) emitter: Emitter<T>,
 }
 
 #[cfg(feature = "smol")]
@@ -49,20 +352,34 @@ pub struct TerminalReporter<T: RawStream + AsLockedWrite> {
 #[derive(Debug)]
 pub struct TerminalReporter<T: RawStream + AsLockedWrite + Send + 'static> {
     diagnostics: Mutex<Vec<Diagnostic>>,
-    lookups: Mutex<SlotMap<LookupKey, (String, Lookup)>>,
+    pub(crate) lookups: Mutex<Lookups>,
     filter: LevelFilter,
-    emitter: Emitter<T>,
+    policy: LevelPolicy,
+    window: SnippetWindow,
+    terminal: bool,
+    theme: Theme,
+    summarize: bool,
+    dedup: bool,
+    seen: Mutex<HashSet<Diagnostic>>,
+    suppressions: Option<Suppressions>,
+    allows: Mutex<Allows>,
+    max_errors: Option<usize>,
+    errors: Mutex<ErrorCount>,
+    order: SortOrder,
+    grouped: bool,
+    streaming: bool,
+    streamed_errors: AtomicBool,
+    stream_error: Mutex<Option<std::io::Error>>,
+    emitted: [AtomicUsize; 4],
+    emitter: Mutex<Emitter<T>>,
 }
 
+// `.into()` wraps the inner collections in a `Mutex` when `smol` is enabled.
+#[allow(clippy::useless_conversion)]
 impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
     /// Creates an empty `TerminalReporter` with the given emitter.
     pub fn new(emitter: T) -> TerminalReporter<T> {
-        TerminalReporter {
-            diagnostics: Vec::new().into(),
-            lookups: SlotMap::with_key().into(),
-            filter: LevelFilter::Debug,
-            emitter: new_emitter(emitter),
-        }
+        TerminalReporter::filtered(emitter, LevelFilter::Debug)
     }
 
     /// Creates an empty `TerminalReporter` with the given emitter and filter level.
@@ -71,7 +388,24 @@ impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
             diagnostics: Vec::new().into(),
             lookups: SlotMap::with_key().into(),
             filter,
-            emitter: new_emitter(emitter),
+            policy: LevelPolicy::default(),
+            window: SnippetWindow::default(),
+            terminal: emitter.is_terminal(),
+            theme: Theme::default(),
+            summarize: false,
+            dedup: false,
+            seen: HashSet::new().into(),
+            suppressions: None,
+            allows: SecondaryMap::new().into(),
+            max_errors: None,
+            order: SortOrder::default(),
+            grouped: false,
+            errors: ErrorCount::default().into(),
+            streaming: false,
+            streamed_errors: AtomicBool::new(false),
+            stream_error: None.into(),
+            emitted: Default::default(),
+            emitter: new_emitter(emitter).into(),
         }
     }
 
@@ -87,10 +421,338 @@ impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
         self.filter = filter;
         self
     }
+
+    /// Sets the policy used to change the level of diagnostics as they are reported or emitted.
+    ///
+    /// The policy is applied before diagnostics are stored,
+    /// so [`has_errors`](TerminalReporter::has_errors) takes promoted diagnostics into account.
+    #[inline]
+    pub fn set_policy(&mut self, policy: LevelPolicy) {
+        self.policy = policy;
+    }
+
+    /// Returns the initial terminal reporter with the level policy set to the given policy.
+    #[inline]
+    pub fn with_policy(mut self, policy: LevelPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the window of source lines printed around spanned diagnostics.
+    #[inline]
+    pub fn set_window(&mut self, window: SnippetWindow) {
+        self.window = window;
+    }
+
+    /// Returns the initial terminal reporter with the snippet window set to the given window.
+    #[inline]
+    pub fn with_window(mut self, window: SnippetWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets the colors and glyphs used to format diagnostics.
+    #[inline]
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Returns the initial terminal reporter with the theme set to the given theme.
+    #[inline]
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets whether a summary of the emitted diagnostics
+    /// is printed at the end of [`emit_all`](TerminalReporter::emit_all).
+    ///
+    /// See [`summary`](TerminalReporter::summary) for the format of the summary.
+    #[inline]
+    pub fn set_summary(&mut self, summarize: bool) {
+        self.summarize = summarize;
+    }
+
+    /// Returns the initial terminal reporter with the summary enabled or disabled.
+    ///
+    /// See [`set_summary`](TerminalReporter::set_summary) for more information.
+    #[inline]
+    pub fn with_summary(mut self, summarize: bool) -> Self {
+        self.summarize = summarize;
+        self
+    }
+
+    /// Sets whether diagnostics equal to one that was already reported or emitted are dropped.
+    ///
+    /// Diagnostics are only dropped if they are exact repeats,
+    /// with the same level, message, span, notes, lint, and code.
+    /// Repeats are only tracked until the next call to [`emit_all`](TerminalReporter::emit_all),
+    /// so a long-lived reporter emits a diagnostic again when it reappears in a later batch.
+    #[inline]
+    pub fn set_dedup(&mut self, dedup: bool) {
+        self.dedup = dedup;
+    }
+
+    /// Returns the initial terminal reporter with deduplication enabled or disabled.
+    ///
+    /// See [`set_dedup`](TerminalReporter::set_dedup) for more information.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut reporter = TerminalReporter::default().with_dedup(true);
+    /// let file = reporter.register_file("example.txt", "(1 + 2");
+    ///
+    /// reporter.report(error!(Span::new(file, 0..1), "unmatched parenthesis"));
+    /// reporter.report(error!(Span::new(file, 0..1), "unmatched parenthesis"));
+    /// reporter.report(error!(Span::new(file, 6..6), "unmatched parenthesis"));
+    ///
+    /// reporter.emit_all()?;
+    /// assert_eq!(reporter.error_count(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Checks whether the lint of `diagnostic` is forbidden from being suppressed.
+    fn is_forbidden(&self, diagnostic: &Diagnostic) -> bool {
+        diagnostic.lint.is_some_and(|lint| {
+            self.suppressions
+                .as_ref()
+                .is_some_and(|suppressions| suppressions.is_forbidden(lint))
+        })
+    }
+
+    /// Sets the comment syntax used to suppress lints from within registered files.
+    ///
+    /// Only files registered after the syntax is set are searched for suppression comments.
+    #[inline]
+    pub fn set_suppressions(&mut self, suppressions: Suppressions) {
+        self.suppressions = Some(suppressions);
+    }
+
+    /// Returns the initial terminal reporter with the given suppression comment syntax.
+    ///
+    /// See [`Suppressions`] for more information.
+    #[inline]
+    pub fn with_suppressions(mut self, suppressions: Suppressions) -> Self {
+        self.suppressions = Some(suppressions);
+        self
+    }
+
+    /// Sets the order in which [`emit_all`](TerminalReporter::emit_all) prints reported diagnostics.
+    #[inline]
+    pub fn set_order(&mut self, order: SortOrder) {
+        self.order = order;
+    }
+
+    /// Returns the initial terminal reporter with the sort order set to the given order.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// use nurse::SortOrder;
+    ///
+    /// let mut reporter = TerminalReporter::default().with_order(SortOrder::SpanlessLast);
+    /// ```
+    #[inline]
+    pub fn with_order(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Gets the order in which [`emit_all`](TerminalReporter::emit_all) prints reported diagnostics.
+    #[inline]
+    pub fn order(&self) -> SortOrder {
+        self.order
+    }
+
+    /// Sets whether [`emit_all`](TerminalReporter::emit_all) groups diagnostics by the file of their span.
+    ///
+    /// Each group is preceded by a header with the file's name and its number of errors and warnings,
+    /// and snippets in that file are printed without their `[file:line:col]` line.
+    /// Groups are printed in the order of their first diagnostic,
+    /// followed by the diagnostics without a span.
+    /// Combine with [`set_order`](TerminalReporter::set_order) to order groups by registration.
+    #[inline]
+    pub fn set_grouped(&mut self, grouped: bool) {
+        self.grouped = grouped;
+    }
+
+    /// Returns the initial terminal reporter with grouping by file enabled or disabled.
+    ///
+    /// See [`set_grouped`](TerminalReporter::set_grouped) for more information.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// use nurse::SortOrder;
+    ///
+    /// let mut reporter = TerminalReporter::default()
+    ///     .with_order(SortOrder::SpanlessLast)
+    ///     .with_grouped(true);
+    /// ```
+    #[inline]
+    pub fn with_grouped(mut self, grouped: bool) -> Self {
+        self.grouped = grouped;
+        self
+    }
+
+    /// Sets whether reported diagnostics are printed immediately.
+    ///
+    /// In streaming mode, [`report`](TerminalReporter::report) prints each diagnostic as soon as it is reported,
+    /// while still counting it towards [`has_errors`](TerminalReporter::has_errors)
+    /// and the [summary](TerminalReporter::set_summary) printed by [`emit_all`](TerminalReporter::emit_all).
+    /// Streamed diagnostics are not stored, so they are neither [sorted](TerminalReporter::set_order)
+    /// nor [grouped](TerminalReporter::set_grouped).
+    ///
+    /// I/O errors encountered while streaming are returned by the next call to [`emit_all`](TerminalReporter::emit_all).
+    #[inline]
+    pub fn set_streaming(&mut self, streaming: bool) {
+        self.streaming = streaming;
+    }
+
+    /// Returns the initial terminal reporter with streaming enabled or disabled.
+    ///
+    /// See [`set_streaming`](TerminalReporter::set_streaming) for more information.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut reporter = TerminalReporter::default()
+    ///     .with_streaming(true)
+    ///     .with_summary(true);
+    ///
+    /// // Printed immediately
+    /// reporter.report(error!("something went wrong"));
+    /// assert!(reporter.has_errors());
+    ///
+    /// // Prints the summary
+    /// reporter.emit_all()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+
+    /// Sets the maximum number of [`Error`](Level::Error) level diagnostics stored by the reporter.
+    ///
+    /// Once the maximum is reached, [`report`](TerminalReporter::report) returns [`ReportFlow::Abort`]
+    /// so that the caller can stop early, and further errors are counted but not stored.
+    /// A final "too many errors" diagnostic is printed by [`emit_all`](TerminalReporter::emit_all).
+    ///
+    /// Errors printed directly through [`emit`](TerminalReporter::emit) are not limited.
+    #[inline]
+    pub fn set_max_errors(&mut self, max_errors: Option<usize>) {
+        self.max_errors = max_errors;
+    }
+
+    /// Returns the initial terminal reporter with the maximum error count set to the given maximum.
+    ///
+    /// See [`set_max_errors`](TerminalReporter::set_max_errors) for more information.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// use nurse::ReportFlow;
+    ///
+    /// let mut reporter = TerminalReporter::default().with_max_errors(Some(2));
+    ///
+    /// assert_eq!(reporter.report(error!("first")), ReportFlow::Continue);
+    /// assert!(reporter.report(error!("second")).is_abort());
+    /// assert!(reporter.report(error!("third")).is_abort());
+    /// ```
+    #[inline]
+    pub fn with_max_errors(mut self, max_errors: Option<usize>) -> Self {
+        self.max_errors = max_errors;
+        self
+    }
+
+    /// Returns the number of diagnostics of the given `level` that have been emitted so far.
+    ///
+    /// Diagnostics hidden by the reporter's filter are not counted,
+    /// see [`summary`](TerminalReporter::summary) for the diagnostics that are left out.
+    #[inline]
+    pub fn emitted(&self, level: Level) -> usize {
+        self.emitted[level.index()].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of [`Error`](Level::Error) level diagnostics that have been emitted so far.
+    #[inline]
+    pub fn error_count(&self) -> usize {
+        self.emitted(Level::Error)
+    }
+
+    /// Returns the number of [`Warn`](Level::Warn) level diagnostics that have been emitted so far.
+    #[inline]
+    pub fn warning_count(&self) -> usize {
+        self.emitted(Level::Warn)
+    }
+
+    /// Creates a diagnostic summarizing the errors and warnings emitted so far,
+    /// returning `None` if there were neither.
+    ///
+    /// Only diagnostics that were printed are counted,
+    /// so diagnostics hidden by the reporter's filter, suppressed, deduplicated,
+    /// or dropped after the [maximum error count](TerminalReporter::set_max_errors) was reached are left out.
+    /// Dropped errors are instead mentioned by the "too many errors" diagnostic.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut reporter = TerminalReporter::default();
+    ///
+    /// reporter.emit(error!("first"))?;
+    /// reporter.emit(error!("second"))?;
+    /// reporter.emit(warning!("third"))?;
+    ///
+    /// let summary = reporter.summary().unwrap();
+    /// assert_eq!(summary.message(), "aborting due to 2 previous errors; 1 warning emitted");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summary(&self) -> Option<Diagnostic> {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let errors = self.error_count();
+        let warnings = self.warning_count();
+
+        match (errors, warnings) {
+            (0, 0) => None,
+            (0, warnings) => Some(Diagnostic::warning(format!(
+                "{warnings} warning{} emitted",
+                plural(warnings)
+            ))),
+            (errors, 0) => Some(Diagnostic::error(format!(
+                "aborting due to {errors} previous error{}",
+                plural(errors)
+            ))),
+            (errors, warnings) => Some(Diagnostic::error(format!(
+                "aborting due to {errors} previous error{}; {warnings} warning{} emitted",
+                plural(errors),
+                plural(warnings)
+            ))),
+        }
+    }
 }
 
 #[cfg(not(feature = "smol"))]
-impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
+impl<T: RawStream + AsLockedWrite + Send> TerminalReporter<T> {
     /// Inserts a file into the lookup table with the given filename and contents,
     /// returning the [`LookupKey`] associated with it.
     /// This lookup key must only be used with the reporter it was registered with.
@@ -98,15 +760,179 @@ impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
     /// This operation can be computationally intensive,
     /// depending on the file size.
     pub fn register_file<N: ToString, F: ToString>(&mut self, name: N, contents: F) -> LookupKey {
+        let lookup = Lookup::new(contents.to_string());
+        let allows = self
+            .suppressions
+            .as_ref()
+            .map(|suppressions| suppressions.parse(&lookup));
+
+        let key = self.lookups.insert((name.to_string(), lookup));
+        if let Some(allows) = allows {
+            self.allows.insert(key, allows);
+        }
+
+        key
+    }
+
+    /// Reads the file at `path` and inserts it into the lookup table, named after its path,
+    /// returning the [`LookupKey`] associated with it.
+    ///
+    /// If a file has already been registered under the same name,
+    /// its key is returned without reading the file again.
+    /// This allows paths to be resolved repeatedly, e.g. when parsing the output of an external tool,
+    /// and files to be registered from memory ahead of time with [`register_file`](TerminalReporter::register_file).
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file can't be read or isn't valid UTF-8.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # use nurse::prelude::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// let mut reporter = TerminalReporter::default();
+    /// let key = reporter.register_path("src/main.c")?;
+    ///
+    /// assert_eq!(reporter.register_path("src/main.c")?, key);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_path<P: AsRef<Path>>(&mut self, path: P) -> io::Result<LookupKey> {
+        let path = path.as_ref();
+        let name = path.to_string_lossy();
+        if let Some(key) = self.find_file(&name) {
+            return Ok(key);
+        }
+
+        let contents = std::fs::read_to_string(path)?;
+        Ok(self.register_file(name, contents))
+    }
+
+    /// Returns the key of a registered file with the given name and contents,
+    /// registering it if there is none.
+    #[cfg(feature = "miette")]
+    pub(crate) fn find_or_register(&mut self, name: &str, contents: &str) -> LookupKey {
+        // Registered sources have their tabs replaced
+        let contents = contents.replace('\t', " ");
+        let existing = self
+            .lookups
+            .iter()
+            .find(|(_, (file, lookup))| file == name && lookup.source() == contents);
+
+        match existing {
+            Some((key, _)) => key,
+            None => self.register_file(name, contents),
+        }
+    }
+
+    /// Returns the key of the first registered file with the given name.
+    pub(crate) fn find_file(&self, name: &str) -> Option<LookupKey> {
         self.lookups
-            .insert((name.to_string(), Lookup::new(contents.to_string())))
+            .iter()
+            .find(|(_, (file, _))| file == name)
+            .map(|(key, _)| key)
+    }
+
+    /// Gets the name and lookup table of the file referred to by `key`.
+    // Only used by some of the optional integrations
+    #[allow(dead_code)]
+    pub(crate) fn file(&self, key: LookupKey) -> Option<(&str, &Lookup)> {
+        self.lookups
+            .get(key)
+            .map(|(name, lookup)| (name.as_str(), lookup))
+    }
+
+    /// Creates a span from `start` to `end` in the file referred to by `key`,
+    /// returning `None` if the file isn't registered or the range doesn't lie on character boundaries within it.
+    // Only used by some of the optional integrations
+    #[allow(dead_code)]
+    pub(crate) fn checked_span(&self, key: LookupKey, start: usize, end: usize) -> Option<Span> {
+        let (_, lookup) = self.file(key)?;
+        let source = lookup.source();
+
+        (start <= end && source.is_char_boundary(start) && source.is_char_boundary(end)).then_some(
+            Span {
+                lookup: key,
+                start,
+                end,
+            },
+        )
+    }
+
+    /// Checks whether `diagnostic` is suppressed by a comment in its file,
+    /// returning an error for each comment suppressing a forbidden lint instead.
+    fn is_suppressed(&mut self, diagnostic: &Diagnostic) -> Result<bool, Vec<Diagnostic>> {
+        let Some(span) = diagnostic.span else {
+            return Ok(false);
+        };
+        let forbidden = self.is_forbidden(diagnostic);
+        let (Some((_, lookup)), Some(allows)) = (
+            self.lookups.get(span.lookup),
+            self.allows.get_mut(span.lookup),
+        ) else {
+            return Ok(false);
+        };
+
+        suppression::suppress(allows, lookup, span.lookup, diagnostic, forbidden)
+    }
+
+    /// Checks whether `diagnostic` repeats one already seen when deduplication is enabled.
+    fn is_repeat(&mut self, diagnostic: &Diagnostic) -> bool {
+        self.dedup && !self.seen.insert(diagnostic.clone())
+    }
+
+    /// Sorts the reported diagnostics by their file name, span, level, message, code, lint, and notes,
+    /// so that their order no longer depends on the order they were reported in.
+    pub(crate) fn sort_by_contents(&mut self) {
+        let lookups = &self.lookups;
+        let span = |span: Option<Span>| {
+            span.map(|span| {
+                let name = lookups.get(span.lookup).map(|(name, _)| name.as_str());
+                (name, span.start, span.end)
+            })
+        };
+
+        self.diagnostics.sort_by_cached_key(|diagnostic| {
+            let notes: Vec<_> = diagnostic
+                .notes
+                .iter()
+                .map(|note| (note.value.clone(), span(note.span), note.help))
+                .collect();
+
+            (
+                span(diagnostic.span),
+                diagnostic.level.index(),
+                diagnostic.message.clone(),
+                diagnostic.code.clone(),
+                diagnostic.lint,
+                notes,
+            )
+        });
     }
 
     /// Prints a diagnostic to the given emitter,
     /// generally [`Stdout`](std::io::Stdout).
+    ///
+    /// Diagnostics suppressed by a comment in their file,
+    /// or repeating an earlier diagnostic when [deduplication](TerminalReporter::set_dedup) is enabled, are dropped.
     pub fn emit(&mut self, diagnostic: Diagnostic) -> io::Result<()> {
+        let diagnostic = self.policy.apply(diagnostic);
+        let suppressed = match self.is_suppressed(&diagnostic) {
+            Ok(suppressed) => suppressed,
+            Err(errors) => {
+                for error in errors {
+                    self.emit(error)?;
+                }
+                false
+            }
+        };
+        if suppressed || self.is_repeat(&diagnostic) {
+            return Ok(());
+        }
+
         if self.filter.passes(diagnostic.level) {
-            return self.emit_fancy(diagnostic);
+            return self.emit_fancy(diagnostic, None);
         }
 
         Ok(())
@@ -116,148 +942,89 @@ impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
     /// generally [`Stdout`](std::io::Stdout).
     ///
     /// Clears the store of reported diagnostics,
-    /// causing subsequent calls not to repeat already emitted diagnostics.
+    /// causing subsequent calls not to repeat already emitted diagnostics,
+    /// and forgets the diagnostics seen for [deduplication](TerminalReporter::set_dedup).
     pub fn emit_all(&mut self) -> io::Result<()> {
-        let mut result = Ok(());
+        let mut result = match self.stream_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        };
+        self.streamed_errors.store(false, Ordering::Relaxed);
+        self.seen.clear();
 
         let mut diagnostics = Vec::new();
         std::mem::swap(&mut diagnostics, &mut self.diagnostics);
+        let diagnostics: Vec<Diagnostic> = self
+            .order
+            .sort(diagnostics, &self.lookups)
+            .into_iter()
+            .filter(|diagnostic| self.filter.passes(diagnostic.level))
+            .collect();
+
+        let groups = if self.grouped {
+            group(diagnostics)
+        } else {
+            vec![(None, diagnostics)]
+        };
 
-        for diagnostic in diagnostics {
-            if !self.filter.passes(diagnostic.level) {
-                continue;
+        for (key, diagnostics) in groups {
+            if let Some(key) = key {
+                let header = format_header(&self.lookups[key].0, &diagnostics, &self.theme);
+                if let Err(err) = write!(self.emitter, "{header}") {
+                    result = Err(err);
+                }
             }
 
-            if let Err(err) = self.emit_fancy(diagnostic) {
-                result = Err(err);
+            for diagnostic in diagnostics {
+                if let Err(err) = self.emit_fancy(diagnostic, key) {
+                    result = Err(err);
+                }
             }
         }
 
-        result
-    }
-
-    fn emit_fancy(&mut self, diagnostic: Diagnostic) -> io::Result<()> {
-        let mut note_offset = diagnostic.level.title().len() + 1;
-        let message = diagnostic.format_message();
-        writeln!(self.emitter, "{message}")?;
-
-        let note = match diagnostic.note {
-            Some(Note {
-                ref value,
-                span: Some(span),
-            }) => Some((value.as_str(), span)),
-            _ => None,
-        };
-
-        if let Some(span) = diagnostic.span {
-            let (pointer, offset) =
-                Self::pointer(&self.lookups, span, note, diagnostic.level.color());
-            note_offset = offset + 1;
-            writeln!(self.emitter, "{pointer}")?;
-        }
-
-        let note = diagnostic.note.is_some();
-        if let Some(Note { value, span: None }) = diagnostic.note {
-            writeln!(
-                self.emitter,
-                "{:>note_offset$} {}: {}",
-                "=".bright_blue().bold(),
-                "note".bold(),
-                value
-            )?;
+        if let Some(abort) = self.errors.announce(self.max_errors) {
+            let formatted = format_diagnostic(
+                &self.lookups,
+                &abort,
+                None,
+                self.window,
+                self.terminal,
+                &self.theme,
+            );
+            if let Err(err) = write!(self.emitter, "{formatted}") {
+                result = Err(err);
+            }
         }
 
-        if diagnostic.span.is_some() || note {
-            writeln!(self.emitter)?;
+        if let Some(summary) = self.summary().filter(|_| self.summarize) {
+            let formatted = format_diagnostic(
+                &self.lookups,
+                &summary,
+                None,
+                self.window,
+                self.terminal,
+                &self.theme,
+            );
+            if let Err(err) = write!(self.emitter, "{formatted}") {
+                result = Err(err);
+            }
         }
 
-        Ok(())
+        result
     }
 
-    fn pointer(
-        lookups: &SlotMap<LookupKey, (String, Lookup)>,
-        span: Span,
-        note: Option<(&str, Span)>,
-        arrow_color: Color,
-    ) -> (String, usize) {
-        let (file, lookup) = lookups.get(span.lookup()).expect("span should ");
-
-        let lines = lookup.lines(span.start()..span.end());
-        let line_n = lines.start + 1;
-        let col_n = lookup.col_from_line(lines.start, span.start()) + 1;
-
-        if lines.len() > 1 {
-            let start = lookup.line(lines.start).trim_end();
-            let end = lookup.line(lines.end - 1).trim_end();
-
-            let end_col = lookup.col_from_line(lines.end - 1, span.end());
-
-            let offset = ((lines.end + 1).ilog10()).max(2) as usize + 2;
-
-            (
-                format!(
-                    "\
-                    {arrow:>arr_space$} [{name}:{line_n}:{col_n}]\n\
-                    {cap:>width$}\n\
-                    {start_n}   {start}\n\
-                    {cap:>width$} {start_pointer}\n\
-                    {dot_n}  {pipe}\n\
-                    {end_n} {pipe} {end}\n\
-                    {cap:>width$} {end_pointer}\n\
-                    ",
-                    arrow = "——>".bright_blue().bold(),
-                    arr_space = offset + 2,
-                    name = file.bold().bright_cyan().underline(),
-                    cap = "┃".bright_blue().bold(),
-                    width = offset + 1,
-                    start_n = format!("{:<offset$}┃", lines.start + 1)
-                        .bright_blue()
-                        .bold(),
-                    dot_n = format!("{:<offset$}", "...").bright_blue().bold(),
-                    end_n = format!("{:<offset$}┃", lines.end + 1).bright_blue().bold(),
-                    start_pointer = format!(
-                        "╭─{blank:·>start$}{blank:—>length$}",
-                        blank = "",
-                        start = col_n - 1,
-                        length = start.len() - col_n + 1,
-                    )
-                    .color(arrow_color),
-                    end_pointer = format!("╰─{blank:─>length$}", blank = "", length = end_col)
-                        .color(arrow_color),
-                    pipe = "│".color(arrow_color)
-                ),
-                offset,
-            )
-        } else {
-            let line = lookup.line(lines.start).trim_end();
-            let offset = (lines.start + 1).ilog10() as usize + 2;
-
-            (
-                format!(
-                    "\
-                    {arrow:>arr_space$} [{name}:{line_n}:{col_n}]\n\
-                    {cap:>width$}\n\
-                    {n}{cap} {line}\n\
-                    {cap:>width$} {pointer}\
-                    ",
-                    arrow = "——>".bright_blue().bold(),
-                    name = file.bold().bright_cyan().underline(),
-                    cap = "┃".bright_blue().bold(),
-                    width = offset + 1,
-                    arr_space = offset + 2,
-                    n = format!("{line_n:<offset$}").bright_blue().bold(),
-                    pointer = format!(
-                        "{blank:>start$}{blank:‾>length$}",
-                        blank = "",
-                        start = col_n - 1,
-                        length = span.end() - span.start(),
-                    )
-                    .bold()
-                    .color(arrow_color),
-                ),
-                offset,
-            )
-        }
+    fn emit_fancy(&mut self, diagnostic: Diagnostic, group: Option<LookupKey>) -> io::Result<()> {
+        self.emitted[diagnostic.level.index()].fetch_add(1, Ordering::Relaxed);
+
+        let formatted = format_diagnostic(
+            &self.lookups,
+            &diagnostic,
+            group,
+            self.window,
+            self.terminal,
+            &self.theme,
+        );
+        write!(self.emitter, "{formatted}")
     }
 
     /// Gets the line-column location of the span in its file.
@@ -287,26 +1054,159 @@ impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
         Location { line, column }
     }
 
+    /// Converts a line-column `location` in the file referred to by `key` into a character index,
+    /// the inverse of [`location`](TerminalReporter::location).
+    ///
+    /// Returns an error if the location lies outside of the file.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if `key` refers to a file not registered with this reporter.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::{prelude::*, Location, LocationError};
+    /// # let mut reporter = TerminalReporter::default();
+    /// let key = reporter.register_file("example.txt", "foo\nbar");
+    ///
+    /// assert_eq!(reporter.offset(key, Location { line: 1, column: 2 }), Ok(6));
+    /// assert!(reporter.offset(key, Location { line: 2, column: 0 }).is_err());
+    /// ```
+    pub fn offset(&self, key: LookupKey, location: Location) -> Result<usize, LocationError> {
+        let (_, lookup) = self
+            .lookups
+            .get(key)
+            .expect("key should refer to an already registered file");
+
+        lookup.offset(location)
+    }
+
     /// Adds the provided `diagnostic` to the inner collection.
     ///
     /// Will be emitted when [`emit_all`](TerminalReporter::emit_all) is called.
-    pub fn report(&mut self, diagnostic: Diagnostic) {
-        self.diagnostics.push(diagnostic);
+    /// Diagnostics suppressed by a comment in their file,
+    /// or repeating an earlier diagnostic when [deduplication](TerminalReporter::set_dedup) is enabled, are dropped.
+    ///
+    /// Returns [`ReportFlow::Abort`] once the [maximum error count](TerminalReporter::set_max_errors) has been reached.
+    pub fn report(&mut self, diagnostic: Diagnostic) -> ReportFlow {
+        let diagnostic = self.policy.apply(diagnostic);
+        let suppressed = match self.is_suppressed(&diagnostic) {
+            Ok(suppressed) => suppressed,
+            Err(errors) => {
+                self.report_all(errors);
+                false
+            }
+        };
+        if !suppressed
+            && !self.is_repeat(&diagnostic)
+            && (!diagnostic.is_error() || self.errors.count(self.max_errors))
+        {
+            if self.streaming {
+                self.stream(diagnostic);
+            } else {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        self.errors.signal(self.max_errors)
+    }
+
+    /// Prints a reported diagnostic immediately,
+    /// holding on to any I/O error until the next call to [`emit_all`](TerminalReporter::emit_all).
+    fn stream(&mut self, diagnostic: Diagnostic) {
+        if diagnostic.is_error() {
+            self.streamed_errors.store(true, Ordering::Relaxed);
+        }
+
+        if self.filter.passes(diagnostic.level) {
+            if let Err(err) = self.emit_fancy(diagnostic, None) {
+                self.stream_error = Some(err);
+            }
+        }
+    }
+
+    /// Reports a warning for every suppression comment that hasn't suppressed a diagnostic,
+    /// if [unused suppressions are warned about](Suppressions::set_warn_unused).
+    ///
+    /// Suppressions can match diagnostics reported at any point,
+    /// so this should be called once every diagnostic has been reported, before the final [`emit_all`](TerminalReporter::emit_all).
+    /// The warnings are [reported](TerminalReporter::report) like any other diagnostic,
+    /// so they are subject to the reporter's policy, deduplication, sort order, and counts.
+    /// Each suppression is only warned about once.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::prelude::*;
+    /// # fn main() -> std::io::Result<()> {
+    /// use nurse::Suppressions;
+    ///
+    /// let mut reporter = TerminalReporter::default()
+    ///     .with_suppressions(Suppressions::new(";").with_warn_unused(true));
+    /// reporter.register_file("main.asm", "loop: ; nurse-allow(unused_label)\n");
+    ///
+    /// reporter.report_unused_suppressions();
+    /// reporter.emit_all()?;
+    /// assert_eq!(reporter.warning_count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn report_unused_suppressions(&mut self) -> ReportFlow {
+        if !self
+            .suppressions
+            .as_ref()
+            .is_some_and(Suppressions::warn_unused)
+        {
+            return self.errors.signal(self.max_errors);
+        }
+
+        let mut warnings = Vec::new();
+        for (key, allows) in self.allows.iter_mut() {
+            warnings.extend(suppression::unused(allows, key));
+        }
+
+        self.report_all(warnings)
+    }
+
+    /// Signals whether the maximum error count is reached once `pending` more errors are reported.
+    pub(crate) fn signal_pending(&self, pending: usize) -> ReportFlow {
+        match self.max_errors {
+            Some(max) if self.errors.reported + pending >= max => ReportFlow::Abort,
+            _ => ReportFlow::Continue,
+        }
+    }
+
+    /// Creates a scoped buffer that holds back reported diagnostics
+    /// until it is committed into this reporter.
+    ///
+    /// See [`DiagnosticBuffer`] for more information.
+    pub fn buffer(&mut self) -> DiagnosticBuffer<'_, T> {
+        DiagnosticBuffer::new(self)
     }
 
     /// Adds the provided list of `diagnostics` to the inner collection.
     ///
     /// All will be emitted when [`emit_all`](TerminalReporter::emit_all) is called.
-    pub fn report_all(&mut self, mut diagnostics: Vec<Diagnostic>) {
-        self.diagnostics.append(&mut diagnostics);
+    ///
+    /// Returns [`ReportFlow::Abort`] once the [maximum error count](TerminalReporter::set_max_errors) has been reached.
+    pub fn report_all(&mut self, diagnostics: Vec<Diagnostic>) -> ReportFlow {
+        for diagnostic in diagnostics {
+            let _ = self.report(diagnostic);
+        }
+
+        self.errors.signal(self.max_errors)
     }
 
     /// Returns `true` if any diagnostics in the inner collection are of level [`Error`](crate::Level::Error),
+    /// or if an error has been [streamed](TerminalReporter::set_streaming) since the last call to [`emit_all`](TerminalReporter::emit_all),
     /// otherwise returns `false`.
     pub fn has_errors(&self) -> bool {
-        self.diagnostics
-            .iter()
-            .any(|diagnostic| diagnostic.is_error())
+        self.streamed_errors.load(Ordering::Relaxed)
+            || self
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.is_error())
     }
 
     /// Returns `true` if there are no diagnostics stored in the inner collection,
@@ -315,10 +1215,12 @@ impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
         self.diagnostics.is_empty()
     }
 
-    /// Returns a single character-wide span at the end of the file referred to by `key`.
+    /// Returns a zero-width span at the end of the file referred to by `key`.
     ///
     /// This is useful raising errors if you expect a token,
     /// but instead find the end of a file.
+    /// If the file ends with a line break the span is placed before it,
+    /// so that it is shown at the end of the last line of text.
     ///
     /// ## Panics
     ///
@@ -350,11 +1252,11 @@ impl<T: RawStream + AsLockedWrite> TerminalReporter<T> {
             .get(key)
             .expect("key should refer to an already registered file");
 
-        let eof = lookup.file_len();
+        let eof = lookup.eof();
 
         Span {
             start: eof,
-            end: eof + 1,
+            end: eof,
             lookup: key,
         }
     }
@@ -369,33 +1271,164 @@ impl<T: RawStream + AsLockedWrite + Send + 'static> TerminalReporter<T> {
     /// This operation can be computationally intensive,
     /// depending on the file size.
     pub async fn register_file<N: ToString, F: ToString>(&self, name: N, contents: F) -> LookupKey {
+        let lookup = Lookup::new(contents.to_string());
+        let allows = self
+            .suppressions
+            .as_ref()
+            .map(|suppressions| suppressions.parse(&lookup));
+
         let mut lookups = self.lookups.lock().await;
-        lookups.insert((name.to_string(), Lookup::new(contents.to_string())))
+        let key = lookups.insert((name.to_string(), lookup));
+        if let Some(allows) = allows {
+            self.allows.lock().await.insert(key, allows);
+        }
+
+        key
     }
 
-    // Adds the provided `diagnostic` to the inner collection.
+    /// Reads the file at `path` and inserts it into the lookup table, named after its path,
+    /// returning the [`LookupKey`] associated with it.
+    ///
+    /// If a file has already been registered under the same name,
+    /// its key is returned without reading the file again.
+    /// This allows paths to be resolved repeatedly, e.g. when parsing the output of an external tool,
+    /// and files to be registered from memory ahead of time with [`register_file`](TerminalReporter::register_file).
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the file can't be read or isn't valid UTF-8.
+    pub async fn register_path<P: AsRef<Path>>(&self, path: P) -> std::io::Result<LookupKey> {
+        let path = path.as_ref();
+        let name = path.to_string_lossy();
+        let existing = self
+            .lookups
+            .lock()
+            .await
+            .iter()
+            .find(|(_, (file, _))| *file == name)
+            .map(|(key, _)| key);
+        if let Some(key) = existing {
+            return Ok(key);
+        }
+
+        let contents = smol::fs::read_to_string(path).await?;
+        Ok(self.register_file(name, contents).await)
+    }
+
+    /// Checks whether `diagnostic` is suppressed by a comment in its file,
+    /// returning an error for each comment suppressing a forbidden lint instead.
+    async fn is_suppressed(&self, diagnostic: &Diagnostic) -> Result<bool, Vec<Diagnostic>> {
+        let Some(span) = diagnostic.span else {
+            return Ok(false);
+        };
+
+        let lookups = self.lookups.lock().await;
+        let mut allows = self.allows.lock().await;
+        let (Some((_, lookup)), Some(allows)) =
+            (lookups.get(span.lookup), allows.get_mut(span.lookup))
+        else {
+            return Ok(false);
+        };
+
+        let forbidden = self.is_forbidden(diagnostic);
+        suppression::suppress(allows, lookup, span.lookup, diagnostic, forbidden)
+    }
+
+    /// Checks whether `diagnostic` repeats one already seen when deduplication is enabled.
+    async fn is_repeat(&self, diagnostic: &Diagnostic) -> bool {
+        self.dedup && !self.seen.lock().await.insert(diagnostic.clone())
+    }
+
+    /// Adds the provided `diagnostic` to the inner collection.
     ///
     /// Will be emitted when [`emit_all`](TerminalReporter::emit_all) is called.
-    pub async fn report(&self, diagnostic: Diagnostic) {
-        let mut diagnostics = self.diagnostics.lock().await;
-        diagnostics.push(diagnostic);
+    /// Diagnostics suppressed by a comment in their file,
+    /// or repeating an earlier diagnostic when [deduplication](TerminalReporter::set_dedup) is enabled, are dropped.
+    ///
+    /// Returns [`ReportFlow::Abort`] once the [maximum error count](TerminalReporter::set_max_errors) has been reached.
+    pub async fn report(&self, diagnostic: Diagnostic) -> ReportFlow {
+        let diagnostic = self.policy.apply(diagnostic);
+        let suppressed = match self.is_suppressed(&diagnostic).await {
+            Ok(suppressed) => suppressed,
+            Err(errors) => {
+                Box::pin(self.report_all(errors)).await;
+                false
+            }
+        };
+        let dropped = suppressed || self.is_repeat(&diagnostic).await;
+
+        let mut errors = self.errors.lock().await;
+        if !dropped && (!diagnostic.is_error() || errors.count(self.max_errors)) {
+            if self.streaming {
+                self.stream(diagnostic).await;
+            } else {
+                let mut diagnostics = self.diagnostics.lock().await;
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        errors.signal(self.max_errors)
+    }
+
+    /// Prints a reported diagnostic immediately,
+    /// holding on to any I/O error until the next call to [`emit_all`](TerminalReporter::emit_all).
+    async fn stream(&self, diagnostic: Diagnostic) {
+        if diagnostic.is_error() {
+            self.streamed_errors.store(true, Ordering::Relaxed);
+        }
+
+        if self.filter.passes(diagnostic.level) {
+            if let Err(err) = self.emit_fancy(diagnostic, None).await {
+                *self.stream_error.lock().await = Some(err);
+            }
+        }
+    }
+
+    /// Reports a warning for every suppression comment that hasn't suppressed a diagnostic,
+    /// if [unused suppressions are warned about](Suppressions::set_warn_unused).
+    ///
+    /// Suppressions can match diagnostics reported at any point,
+    /// so this should be called once every diagnostic has been reported, before the final [`emit_all`](TerminalReporter::emit_all).
+    /// The warnings are [reported](TerminalReporter::report) like any other diagnostic,
+    /// so they are subject to the reporter's policy, deduplication, sort order, and counts.
+    /// Each suppression is only warned about once.
+    pub async fn report_unused_suppressions(&self) -> ReportFlow {
+        if !self
+            .suppressions
+            .as_ref()
+            .is_some_and(Suppressions::warn_unused)
+        {
+            return self.errors.lock().await.signal(self.max_errors);
+        }
+
+        let mut warnings = Vec::new();
+        for (key, allows) in self.allows.lock().await.iter_mut() {
+            warnings.extend(suppression::unused(allows, key));
+        }
+
+        self.report_all(warnings).await
     }
 
     /// Adds the provided list of `diagnostics` to the inner collection.
     ///
     /// All will be emitted when [`emit_all`](TerminalReporter::emit_all) is called.
-    pub async fn report_all(&self, mut diagnostics: Vec<Diagnostic>) {
-        let mut reported = self.diagnostics.lock().await;
-        reported.append(&mut diagnostics);
+    ///
+    /// Returns [`ReportFlow::Abort`] once the [maximum error count](TerminalReporter::set_max_errors) has been reached.
+    pub async fn report_all(&self, diagnostics: Vec<Diagnostic>) -> ReportFlow {
+        for diagnostic in diagnostics {
+            let _ = self.report(diagnostic).await;
+        }
+
+        self.errors.lock().await.signal(self.max_errors)
     }
 
     /// Returns `true` if any diagnostics in the inner collection are of level [`Error`](crate::Level::Error),
+    /// or if an error has been [streamed](TerminalReporter::set_streaming) since the last call to [`emit_all`](TerminalReporter::emit_all),
     /// otherwise returns `false`.
     pub async fn has_errors(&self) -> bool {
         let diagnostics = self.diagnostics.lock().await;
-        diagnostics
-            .iter()
-            .any(|diagnostic| diagnostic.is_error())
+        self.streamed_errors.load(Ordering::Relaxed)
+            || diagnostics.iter().any(|diagnostic| diagnostic.is_error())
     }
 
     /// Returns `true` if there are no diagnostics stored in the inner collection,
@@ -406,82 +1439,150 @@ impl<T: RawStream + AsLockedWrite + Send + 'static> TerminalReporter<T> {
     }
 
     /// Prints a diagnostic to the internal emitter, `stdout` by default.
+    ///
+    /// Diagnostics suppressed by a comment in their file,
+    /// or repeating an earlier diagnostic when [deduplication](TerminalReporter::set_dedup) is enabled, are dropped.
     pub async fn emit(&mut self, diagnostic: Diagnostic) -> std::io::Result<()> {
-        if !self.filter.passes(diagnostic.level) {
+        let diagnostic = self.policy.apply(diagnostic);
+        let suppressed = match self.is_suppressed(&diagnostic).await {
+            Ok(suppressed) => suppressed,
+            Err(errors) => {
+                for error in errors {
+                    Box::pin(self.emit(error)).await?;
+                }
+                false
+            }
+        };
+        if suppressed || self.is_repeat(&diagnostic).await || !self.filter.passes(diagnostic.level)
+        {
             return Ok(());
         }
 
-        self.emit_fancy(diagnostic).await
+        self.emit_fancy(diagnostic, None).await
     }
 
     /// Prints all reported diagnostics to the internal emitter, `stdout` by default.
     ///
     /// Clears the store of reported diagnostics,
-    /// causing subsequent calls not to repeat already emitted diagnostics.
+    /// causing subsequent calls not to repeat already emitted diagnostics,
+    /// and forgets the diagnostics seen for [deduplication](TerminalReporter::set_dedup).
     pub async fn emit_all(&mut self) -> std::io::Result<()> {
-        let mut result = Ok(());
+        let mut result = match self.stream_error.lock().await.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        };
+        self.streamed_errors.store(false, Ordering::Relaxed);
+        self.seen.lock().await.clear();
 
         let mut diagnostics = Vec::new();
         let mut owned_diagnostics = self.diagnostics.lock().await;
 
         std::mem::swap(&mut diagnostics, &mut owned_diagnostics);
         std::mem::drop(owned_diagnostics);
+        let diagnostics: Vec<Diagnostic> = self
+            .order
+            .sort(diagnostics, &*self.lookups.lock().await)
+            .into_iter()
+            .filter(|diagnostic| self.filter.passes(diagnostic.level))
+            .collect();
+
+        let groups = if self.grouped {
+            group(diagnostics)
+        } else {
+            vec![(None, diagnostics)]
+        };
 
-        for diagnostic in diagnostics.into_iter() {
-            if !self.filter.passes(diagnostic.level) {
-                continue;
+        for (key, diagnostics) in groups {
+            if let Some(key) = key {
+                let lookups = self.lookups.lock().await;
+                let header = format_header(&lookups[key].0, &diagnostics, &self.theme);
+                drop(lookups);
+
+                if let Err(err) = self.emitter.lock().await.write_all(header.as_bytes()).await {
+                    result = Err(err);
+                }
             }
 
-            if let Err(err) = self.emit_fancy(diagnostic).await {
-                result = Err(err);
+            for diagnostic in diagnostics {
+                if let Err(err) = self.emit_fancy(diagnostic, key).await {
+                    result = Err(err);
+                }
             }
         }
 
-        result
-    }
-
-    async fn emit_fancy(&mut self, diagnostic: Diagnostic) -> std::io::Result<()> {
-        let mut note_offset = diagnostic.level.title().len() + 1;
-        let message = diagnostic.format_message();
-        self.emitter.write(format!("{message}\n").as_bytes()).await?;
-
-        let note = match diagnostic.note {
-            Some(Note {
-                ref value,
-                span: Some(span),
-            }) => Some((value.as_str(), span)),
-            _ => None,
-        };
-
-        if let Some(span) = diagnostic.span {
-            let (pointer, offset) =
-                self.pointer(span, note, diagnostic.level.color()).await;
-            note_offset = offset + 1;
-            self.emitter
-                .write_all(format!("{pointer}").as_bytes())
-                .await?;
+        let abort = self.errors.lock().await.announce(self.max_errors);
+        if let Some(abort) = abort {
+            let lookups = self.lookups.lock().await;
+            let formatted = format_diagnostic(
+                &lookups,
+                &abort,
+                None,
+                self.window,
+                self.terminal,
+                &self.theme,
+            );
+            drop(lookups);
+
+            if let Err(err) = self
+                .emitter
+                .lock()
+                .await
+                .write_all(formatted.as_bytes())
+                .await
+            {
+                result = Err(err);
+            }
         }
 
-        let note = diagnostic.note.is_some();
-        if let Some(Note { value, span: None }) = diagnostic.note {
-            self.emitter
-                .write_all(
-                    format!(
-                        "{:>note_offset$} {}: {}",
-                        "=".bright_blue().bold(),
-                        "note".bold(),
-                        value
-                    )
-                    .as_bytes(),
-                )
-                .await?;
+        if let Some(summary) = self.summary().filter(|_| self.summarize) {
+            let lookups = self.lookups.lock().await;
+            let formatted = format_diagnostic(
+                &lookups,
+                &summary,
+                None,
+                self.window,
+                self.terminal,
+                &self.theme,
+            );
+            drop(lookups);
+
+            if let Err(err) = self
+                .emitter
+                .lock()
+                .await
+                .write_all(formatted.as_bytes())
+                .await
+            {
+                result = Err(err);
+            }
         }
 
-        if diagnostic.span.is_some() || note {
-            self.emitter.write(b"\n").await?;
-        }
+        result
+    }
 
-        Ok(())
+    async fn emit_fancy(
+        &self,
+        diagnostic: Diagnostic,
+        group: Option<LookupKey>,
+    ) -> std::io::Result<()> {
+        self.emitted[diagnostic.level.index()].fetch_add(1, Ordering::Relaxed);
+
+        let lookups = self.lookups.lock().await;
+        let formatted = format_diagnostic(
+            &lookups,
+            &diagnostic,
+            group,
+            self.window,
+            self.terminal,
+            &self.theme,
+        );
+        drop(lookups);
+
+        self.emitter
+            .lock()
+            .await
+            .write_all(formatted.as_bytes())
+            .await
     }
 
     /// Gets the line-column location of the span in its file.
@@ -513,10 +1614,29 @@ impl<T: RawStream + AsLockedWrite + Send + 'static> TerminalReporter<T> {
         Location { line, column }
     }
 
-    /// Returns a single character-wide span at the end of the file referred to by `key`.
+    /// Converts a line-column `location` in the file referred to by `key` into a character index,
+    /// the inverse of [`location`](TerminalReporter::location).
+    ///
+    /// Returns an error if the location lies outside of the file.
+    ///
+    /// ## Panics
+    ///
+    /// This function will panic if `key` refers to a file not registered with this reporter.
+    pub async fn offset(&self, key: LookupKey, location: Location) -> Result<usize, LocationError> {
+        let lookups = self.lookups.lock().await;
+        let (_, lookup) = lookups
+            .get(key)
+            .expect("key should refer to an already registered file");
+
+        lookup.offset(location)
+    }
+
+    /// Returns a zero-width span at the end of the file referred to by `key`.
     ///
     /// This is useful raising errors if you expect a token,
     /// but instead find the end of a file.
+    /// If the file ends with a line break the span is placed before it,
+    /// so that it is shown at the end of the last line of text.
     ///
     /// ## Panics
     ///
@@ -544,113 +1664,288 @@ impl<T: RawStream + AsLockedWrite + Send + 'static> TerminalReporter<T> {
     /// ```
     pub async fn eof_span(&self, key: LookupKey) -> Span {
         let lookups = self.lookups.lock().await;
-        let (_, lookup) = lookups.get(key)
+        let (_, lookup) = lookups
+            .get(key)
             .expect("key should refer to an already registered file");
 
-        let eof = lookup.file_len();
+        let eof = lookup.eof();
 
         Span {
             start: eof,
-            end: eof + 1,
+            end: eof,
             lookup: key,
         }
     }
+}
 
-    async fn pointer(
-        &self,
-        span: Span,
-        note: Option<(&str, Span)>,
-        arrow_color: Color,
-    ) -> (String, usize) {
-        let lookups = self.lookups.lock().await;
-        let (file, lookup) = lookups.get(span.lookup()).expect("span should ");
-
-        let lines = lookup.lines(span.start()..span.end());
-        let line_n = lines.start + 1;
-        let col_n = lookup.col_from_line(lines.start, span.start()) + 1;
+impl Default for TerminalReporter<std::io::Stdout> {
+    fn default() -> Self {
+        TerminalReporter::new(std::io::stdout())
+    }
+}
 
-        if lines.len() > 1 {
-            let start = lookup.line(lines.start).trim_end();
-            let end = lookup.line(lines.end - 1).trim_end();
+#[cfg(all(test, not(feature = "smol")))]
+mod tests {
+    use super::*;
 
-            let end_col = lookup.col_from_line(lines.end - 1, span.end());
+    /// Gets everything the reporter has printed so far.
+    fn output(reporter: &TerminalReporter<Vec<u8>>) -> String {
+        String::from_utf8(reporter.emitter.as_inner().clone()).unwrap()
+    }
 
-            let offset = ((lines.end + 1).ilog10()).max(2) as usize + 2;
+    /// Gets the first line of each printed diagnostic, e.g. `error: message`, in the order they were printed.
+    fn messages(reporter: &TerminalReporter<Vec<u8>>) -> Vec<String> {
+        output(reporter)
+            .lines()
+            .filter(|line| line.starts_with(char::is_alphabetic))
+            .map(str::to_owned)
+            .collect()
+    }
 
-            (
-                format!(
-                    "\
-                    {arrow:>arr_space$} [{name}:{line_n}:{col_n}]\n\
-                    {cap:>width$}\n\
-                    {start_n}   {start}\n\
-                    {cap:>width$} {start_pointer}\n\
-                    {dot_n}  {pipe}\n\
-                    {end_n} {pipe} {end}\n\
-                    {cap:>width$} {end_pointer}\n\
-                    ",
-                    arrow = "——>".bright_blue().bold(),
-                    arr_space = offset + 2,
-                    name = file.bold().bright_cyan().underline(),
-                    cap = "┃".bright_blue().bold(),
-                    width = offset + 1,
-                    start_n = format!("{:<offset$}┃", lines.start + 1)
-                        .bright_blue()
-                        .bold(),
-                    dot_n = format!("{:<offset$}", "...").bright_blue().bold(),
-                    end_n = format!("{:<offset$}┃", lines.end + 1).bright_blue().bold(),
-                    start_pointer = format!(
-                        "╭─{blank:·>start$}{blank:—>length$}",
-                        blank = "",
-                        start = col_n - 1,
-                        length = start.len() - col_n + 1,
-                    )
-                    .color(arrow_color),
-                    end_pointer = format!("╰─{blank:─>length$}", blank = "", length = end_col)
-                        .color(arrow_color),
-                    pipe = "│".color(arrow_color)
-                ),
-                offset,
-            )
-        } else {
-            let line = lookup.line(lines.start).trim_end();
-            let offset = (lines.start + 1).ilog10() as usize + 2;
+    #[test]
+    fn summary_counts_only_emitted_diagnostics() {
+        let mut reporter = TerminalReporter::filtered(Vec::new(), LevelFilter::Error)
+            .with_max_errors(Some(2))
+            .with_summary(true);
 
-            (
-                format!(
-                    "\
-                    {arrow:>arr_space$} [{name}:{line_n}:{col_n}]\n\
-                    {cap:>width$}\n\
-                    {n}{cap} {line}\n\
-                    {cap:>width$} {pointer}\
-                    ",
-                    arrow = "——>".bright_blue().bold(),
-                    name = file.bold().bright_cyan().underline(),
-                    cap = "┃".bright_blue().bold(),
-                    width = offset + 1,
-                    arr_space = offset + 2,
-                    n = format!("{line_n:<offset$}").bright_blue().bold(),
-                    pointer = format!(
-                        "{blank:>start$}{blank:‾>length$}",
-                        blank = "",
-                        start = col_n - 1,
-                        length = span.end() - span.start(),
-                    )
-                    .bold()
-                    .color(arrow_color),
-                ),
-                offset,
-            )
+        reporter.report(Diagnostic::warning("filtered"));
+        for message in ["first", "second", "dropped"] {
+            reporter.report(Diagnostic::error(message));
         }
+        reporter.emit_all().unwrap();
+
+        assert_eq!(reporter.error_count(), 2);
+        assert_eq!(reporter.warning_count(), 0);
+        assert_eq!(
+            messages(&reporter).last().map(String::as_str),
+            Some("error: aborting due to 2 previous errors")
+        );
     }
-}
 
-impl Default for TerminalReporter<std::io::Stdout> {
-    fn default() -> Self {
-        TerminalReporter {
-            diagnostics: Vec::new().into(),
-            lookups: SlotMap::with_key().into(),
-            filter: LevelFilter::Debug,
-            emitter: new_emitter(std::io::stdout()),
+    #[test]
+    fn max_errors_stops_storing_errors() {
+        let mut reporter = TerminalReporter::new(Vec::new()).with_max_errors(Some(2));
+
+        assert_eq!(
+            reporter.report(Diagnostic::error("first")),
+            ReportFlow::Continue
+        );
+        assert_eq!(
+            reporter.report(Diagnostic::error("second")),
+            ReportFlow::Abort
+        );
+        assert_eq!(
+            reporter.report(Diagnostic::error("third")),
+            ReportFlow::Abort
+        );
+        assert_eq!(
+            reporter.report(Diagnostic::warning("kept")),
+            ReportFlow::Abort
+        );
+        reporter.emit_all().unwrap();
+
+        assert_eq!(
+            messages(&reporter),
+            [
+                "error: first",
+                "error: second",
+                "warning: kept",
+                "error: too many errors, stopping"
+            ]
+        );
+        assert!(output(&reporter).contains("note: 1 further error was not shown"));
+
+        // The announcement is only printed once
+        reporter.emit_all().unwrap();
+        assert_eq!(messages(&reporter).len(), 4);
+    }
+
+    /// Reports diagnostics out of order across two files, with a diagnostic without a span first.
+    fn report_unordered(reporter: &mut TerminalReporter<Vec<u8>>) {
+        let a = reporter.register_file("a.txt", "first file");
+        let b = reporter.register_file("b.txt", "second file");
+
+        reporter.report(Diagnostic::error("spanless"));
+        reporter.report(Diagnostic::spanned_error(Span::new(b, 0..6), "b0"));
+        reporter.report(Diagnostic::spanned_error(Span::new(a, 6..10), "a6"));
+        reporter.report(Diagnostic::spanned_warning(Span::new(a, 0..5), "a0"));
+    }
+
+    #[test]
+    fn sort_orders() {
+        let cases = [
+            (SortOrder::Reported, ["spanless", "b0", "a6", "a0"]),
+            (SortOrder::SpanlessLast, ["a0", "a6", "b0", "spanless"]),
+            (SortOrder::SpanlessInPlace, ["spanless", "a0", "a6", "b0"]),
+        ];
+
+        for (order, expected) in cases {
+            let mut reporter = TerminalReporter::new(Vec::new()).with_order(order);
+            report_unordered(&mut reporter);
+            reporter.emit_all().unwrap();
+
+            let messages: Vec<String> = messages(&reporter)
+                .iter()
+                .map(|line| line.split_once(": ").unwrap().1.to_owned())
+                .collect();
+            assert_eq!(messages, expected, "{order:?}");
         }
     }
+
+    #[test]
+    fn grouped_by_file() {
+        let mut reporter = TerminalReporter::new(Vec::new())
+            .with_order(SortOrder::SpanlessLast)
+            .with_grouped(true);
+        report_unordered(&mut reporter);
+        reporter.emit_all().unwrap();
+
+        let output = output(&reporter);
+        let headers: Vec<&str> = output
+            .lines()
+            .filter(|line| line.starts_with(reporter.theme.glyphs.horizontal))
+            .collect();
+        let rule = reporter.theme.glyphs.horizontal.to_string().repeat(2);
+        assert_eq!(
+            headers,
+            [
+                format!("{rule} a.txt (1 error, 1 warning) {rule}"),
+                format!("{rule} b.txt (1 error) {rule}"),
+            ]
+        );
+
+        // Snippets within a group leave out the name of the file
+        assert!(!output.contains("[a.txt:") && !output.contains("[b.txt:"));
+        assert!(output.trim_end().ends_with("error: spanless"));
+    }
+
+    #[test]
+    fn streaming_prints_when_reported() {
+        let mut reporter = TerminalReporter::new(Vec::new())
+            .with_streaming(true)
+            .with_max_errors(Some(2))
+            .with_order(SortOrder::SpanlessLast);
+
+        reporter.report(Diagnostic::error("first"));
+        assert_eq!(messages(&reporter), ["error: first"]);
+        assert!(reporter.has_errors());
+        assert!(reporter.is_empty());
+
+        reporter.report(Diagnostic::warning("second"));
+        reporter.report(Diagnostic::error("third"));
+        reporter.report(Diagnostic::error("dropped"));
+        assert_eq!(
+            messages(&reporter),
+            ["error: first", "warning: second", "error: third"]
+        );
+
+        reporter.emit_all().unwrap();
+        assert!(!reporter.has_errors());
+        assert_eq!(
+            messages(&reporter).last().map(String::as_str),
+            Some("error: too many errors, stopping")
+        );
+        assert_eq!(reporter.error_count(), 2);
+    }
+
+    #[test]
+    fn unused_suppressions_are_reported_when_finished() {
+        const LINT: &str = "unused_label";
+
+        let mut reporter = TerminalReporter::new(Vec::new())
+            .with_suppressions(Suppressions::new(";").with_warn_unused(true))
+            .with_policy(LevelPolicy::warnings_as_errors("`-D warnings` is enabled"));
+        let key = reporter.register_file(
+            "main.asm",
+            "a: ; nurse-allow(unused_label)\nb: ; nurse-allow(unused_label)\n",
+        );
+
+        reporter.emit_all().unwrap();
+
+        // Reported after the first call to `emit_all`, but still suppressed
+        let late = Diagnostic::spanned_warning(Span::new(key, 0..1), "unused label `a`");
+        reporter.report(late.with_lint(Some(LINT)));
+        assert!(reporter.is_empty());
+
+        reporter.report_unused_suppressions();
+        reporter.report_unused_suppressions();
+        reporter.emit_all().unwrap();
+
+        assert_eq!(
+            messages(&reporter),
+            ["error: unused suppression of `unused_label`"]
+        );
+        assert_eq!(reporter.error_count(), 1);
+        assert!(output(&reporter).contains("main.asm:2:"));
+    }
+
+    #[test]
+    fn forbidden_lints_are_not_suppressed() {
+        use crate::{Lint, LintLevel, LintRegistry};
+
+        const UNUSED_LABEL: Lint = Lint::new("unused_label", LintLevel::Warn);
+
+        let mut lints = LintRegistry::new();
+        lints.register(UNUSED_LABEL);
+        lints
+            .set("unused_label", LintLevel::Forbid, "`-F unused_label`")
+            .unwrap();
+
+        let mut reporter = TerminalReporter::new(Vec::new())
+            .with_suppressions(Suppressions::new(";").with_lints(lints.clone()));
+        let key = reporter.register_file("main.asm", "loop: ; nurse-allow(unused_label)\n");
+        let lint = || {
+            lints
+                .lint(&UNUSED_LABEL, "unused label `loop`")
+                .unwrap()
+                .with_span(Some(Span::new(key, 0..4)))
+        };
+
+        reporter.report(lint());
+        reporter.report(lint());
+        reporter.emit_all().unwrap();
+
+        // The comment is only reported once
+        assert_eq!(
+            messages(&reporter),
+            [
+                "error: suppression of `unused_label` incompatible with forbid",
+                "error: unused label `loop`",
+                "error: unused label `loop`"
+            ]
+        );
+    }
+
+    #[test]
+    fn dedup_is_reset_by_emit_all() {
+        let mut reporter = TerminalReporter::new(Vec::new()).with_dedup(true);
+        let key = reporter.register_file("a.txt", "(1 + 2\n");
+        let diagnostic = Diagnostic::spanned_error(Span::new(key, 0..1), "unmatched parenthesis");
+
+        reporter.report(diagnostic.clone());
+        reporter.report(diagnostic.clone());
+        reporter.emit_all().unwrap();
+        assert_eq!(reporter.error_count(), 1);
+
+        reporter.report(diagnostic.clone());
+        reporter.report(diagnostic);
+        reporter.emit_all().unwrap();
+        assert_eq!(reporter.error_count(), 2);
+    }
+
+    #[test]
+    fn lines_are_not_truncated_when_not_printing_to_a_terminal() {
+        let mut reporter = TerminalReporter::new(Vec::new());
+        let line = format!("let x = {};\n", "1 + ".repeat(100));
+        let key = reporter.register_file("long.rs", &line);
+
+        reporter.report(Diagnostic::spanned_error(
+            Span::new(key, 4..5),
+            "unused variable",
+        ));
+        reporter.emit_all().unwrap();
+
+        assert!(output(&reporter).contains(line.trim_end()));
+        assert!(!output(&reporter).contains("..."));
+    }
 }
@@ -0,0 +1,186 @@
+//! Colors and glyphs used when formatting diagnostics.
+
+pub use colored::Color;
+use colored::{ColoredString, Colorize};
+
+use crate::diagnostic::Level;
+
+/// The appearance of formatted diagnostics.
+///
+/// Two presets are provided: [`Theme::UNICODE`], used by default,
+/// and [`Theme::ASCII`] for terminals and log viewers without box-drawing characters.
+///
+/// ## Example
+///
+/// ```rust
+/// # use nurse::prelude::*;
+/// use nurse::{Color, Theme};
+///
+/// let theme = Theme {
+///     error: Color::Red,
+///     ..Theme::ASCII
+/// };
+///
+/// let reporter = TerminalReporter::default().with_theme(theme);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The color of [`Error`](Level::Error) level diagnostics.
+    pub error: Color,
+    /// The color of [`Warn`](Level::Warn) level diagnostics.
+    pub warning: Color,
+    /// The color of [`Info`](Level::Info) level diagnostics.
+    pub info: Color,
+    /// The color of [`Debug`](Level::Debug) level diagnostics.
+    pub debug: Color,
+    /// The color of secondary labels, such as spanned notes.
+    pub secondary: Color,
+    /// The style of the line number gutter and the arrow pointing to a location.
+    pub gutter: TextStyle,
+    /// The style of file names.
+    pub filename: TextStyle,
+    /// The characters used to draw snippets.
+    pub glyphs: Glyphs,
+}
+
+impl Theme {
+    /// The default theme, drawn using Unicode box-drawing characters.
+    pub const UNICODE: Theme = Theme {
+        error: Color::BrightRed,
+        warning: Color::BrightYellow,
+        info: Color::Cyan,
+        debug: Color::BrightMagenta,
+        secondary: Color::BrightBlue,
+        gutter: TextStyle {
+            color: Some(Color::BrightBlue),
+            bold: true,
+            underline: false,
+        },
+        filename: TextStyle {
+            color: Some(Color::BrightCyan),
+            bold: true,
+            underline: true,
+        },
+        glyphs: Glyphs::UNICODE,
+    };
+
+    /// A theme using the default colors, drawn using only ASCII characters.
+    pub const ASCII: Theme = Theme {
+        glyphs: Glyphs::ASCII,
+        ..Theme::UNICODE
+    };
+
+    /// Gets the color associated with the given `level`.
+    pub fn level(&self, level: Level) -> Color {
+        match level {
+            Level::Error => self.error,
+            Level::Warn => self.warning,
+            Level::Info => self.info,
+            Level::Debug => self.debug,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::UNICODE
+    }
+}
+
+/// The style of a piece of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextStyle {
+    /// The foreground color of the text, or `None` to keep the terminal's color.
+    pub color: Option<Color>,
+    /// Whether the text is bold.
+    pub bold: bool,
+    /// Whether the text is underlined.
+    pub underline: bool,
+}
+
+impl TextStyle {
+    pub(crate) fn paint<S: Into<ColoredString>>(&self, text: S) -> ColoredString {
+        let mut text = text.into();
+
+        if let Some(color) = self.color {
+            text = text.color(color);
+        }
+        if self.bold {
+            text = text.bold();
+        }
+        if self.underline {
+            text = text.underline();
+        }
+
+        text
+    }
+}
+
+/// The characters used to draw snippets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    /// Points to the location of a snippet, before the file name.
+    pub arrow: &'static str,
+    /// Separates the line numbers from the source.
+    pub gutter: char,
+    /// Underlines the primary span of a diagnostic.
+    pub primary_underline: char,
+    /// Underlines secondary spans, such as spanned notes.
+    pub secondary_underline: char,
+    /// Marks zero-width spans.
+    pub insertion_point: char,
+    /// Connects labels and multiline spans to the lines they annotate.
+    pub connector: char,
+    /// Begins a multiline span.
+    pub multiline_start: char,
+    /// Ends a multiline span.
+    pub multiline_end: char,
+    /// Leads from the margin of a multiline span to its columns.
+    pub horizontal: char,
+    /// Fills the columns before the start of a multiline span.
+    pub multiline_lead: char,
+    /// Underlines the first line of a multiline span.
+    pub multiline_underline: char,
+    /// Precedes notes printed after a snippet.
+    pub note: char,
+}
+
+impl Glyphs {
+    /// Unicode box-drawing glyphs.
+    pub const UNICODE: Glyphs = Glyphs {
+        arrow: "——>",
+        gutter: '┃',
+        primary_underline: '‾',
+        secondary_underline: '-',
+        insertion_point: '^',
+        connector: '│',
+        multiline_start: '╭',
+        multiline_end: '╰',
+        horizontal: '─',
+        multiline_lead: '·',
+        multiline_underline: '—',
+        note: '=',
+    };
+
+    /// Glyphs made up only of ASCII characters.
+    pub const ASCII: Glyphs = Glyphs {
+        arrow: "-->",
+        gutter: '|',
+        primary_underline: '^',
+        secondary_underline: '-',
+        insertion_point: '^',
+        connector: '|',
+        multiline_start: '/',
+        multiline_end: '\\',
+        horizontal: '_',
+        multiline_lead: '_',
+        multiline_underline: '^',
+        note: '=',
+    };
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Glyphs::UNICODE
+    }
+}
@@ -4,11 +4,21 @@ use std::{
     ops::{self, Range},
 };
 
+#[cfg(feature = "terminal")]
+use anstream::stream::{AsLockedWrite, RawStream};
+
 use crate::reporter::LookupKey;
+#[cfg(feature = "terminal")]
+use crate::{
+    lookup::{Location, LocationError},
+    reporter::TerminalReporter,
+};
 
 /// A token associated with a [`Span`].
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Spanned<T> {
+    #[cfg_attr(feature = "serde", serde(rename = "value"))]
     inner: T,
     span: Span,
 }
@@ -100,15 +110,19 @@ impl<T> borrow::BorrowMut<T> for Spanned<T> {
 }
 
 #[cfg(not(feature = "terminal"))]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     pub(crate) start: usize,
     pub(crate) end: usize,
 }
 
 /// A range of characters within a file.
+///
+/// With the `serde` feature, spans refer to their file by its registered name,
+/// and can only be serialized or deserialized within `TerminalReporter::serde_scope`.
 #[cfg(feature = "terminal")]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Span {
     pub(crate) lookup: LookupKey,
     // We split the input range into a start and end,
@@ -120,18 +134,26 @@ pub struct Span {
 impl Span {
     /// Gets the start of the span's character range
     pub fn start(&self) -> usize {
-        return self.start;
+        self.start
     }
 
     /// Gets the end of the span's character range
     pub fn end(&self) -> usize {
-        return self.end;
+        self.end
     }
 
     /// Gets the span's character range
     pub fn range(&self) -> Range<usize> {
         self.start..self.end
     }
+
+    /// Checks if the span is zero-width,
+    /// pointing at the position between two characters rather than at a character.
+    ///
+    /// Zero-width spans are rendered with a single caret.
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
 }
 
 #[cfg(feature = "terminal")]
@@ -152,6 +174,79 @@ impl Span {
         self.lookup
     }
 
+    /// Creates a new `Span` between two line-column locations in the file referred to by `file`.
+    ///
+    /// This is useful when positions come from an external source,
+    /// such as a language client or another tool's output.
+    /// Returns an error if either location lies outside of the file,
+    /// or if `start` is located after `end`.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use nurse::{prelude::*, Location};
+    /// # let mut reporter = TerminalReporter::default();
+    /// let file = reporter.register_file("example.txt", "foo\nbar baz");
+    ///
+    /// let start = Location { line: 1, column: 4 };
+    /// let end = Location { line: 1, column: 7 };
+    /// let span = Span::from_locations(&reporter, file, start, end).unwrap();
+    ///
+    /// assert_eq!(span.range(), 8..11);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `file` does not refer to a file registered with `reporter`.
+    ///
+    /// With the `smol` feature enabled, this function is `async`,
+    /// like the rest of [`TerminalReporter`]'s methods.
+    #[cfg(all(feature = "terminal", not(feature = "smol")))]
+    pub fn from_locations<T: RawStream + AsLockedWrite + Send>(
+        reporter: &TerminalReporter<T>,
+        file: LookupKey,
+        start: Location,
+        end: Location,
+    ) -> Result<Span, LocationError> {
+        if start > end {
+            return Err(LocationError::Inverted { start, end });
+        }
+
+        Ok(Span {
+            lookup: file,
+            start: reporter.offset(file, start)?,
+            end: reporter.offset(file, end)?,
+        })
+    }
+
+    /// Creates a new `Span` between two line-column locations in the file referred to by `file`.
+    ///
+    /// This is useful when positions come from an external source,
+    /// such as a language client or another tool's output.
+    /// Returns an error if either location lies outside of the file,
+    /// or if `start` is located after `end`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `file` does not refer to a file registered with `reporter`.
+    #[cfg(all(feature = "terminal", feature = "smol"))]
+    pub async fn from_locations<T: RawStream + AsLockedWrite + Send + 'static>(
+        reporter: &TerminalReporter<T>,
+        file: LookupKey,
+        start: Location,
+        end: Location,
+    ) -> Result<Span, LocationError> {
+        if start > end {
+            return Err(LocationError::Inverted { start, end });
+        }
+
+        Ok(Span {
+            lookup: file,
+            start: reporter.offset(file, start).await?,
+            end: reporter.offset(file, end).await?,
+        })
+    }
+
     /// Creates a new span containing both input spans.
     ///
     /// ## Example
@@ -230,13 +325,20 @@ pub trait MaybeSpanned {
 impl MaybeSpanned for Span {
     #[inline]
     fn get_span(&self) -> Option<Span> {
-        return Some(*self);
+        Some(*self)
     }
 }
 
 impl<T> MaybeSpanned for Spanned<T> {
     #[inline]
     fn get_span(&self) -> Option<Span> {
-        return Some(self.span);
+        Some(self.span)
+    }
+}
+
+impl<T: MaybeSpanned> MaybeSpanned for Option<T> {
+    #[inline]
+    fn get_span(&self) -> Option<Span> {
+        self.as_ref().and_then(MaybeSpanned::get_span)
     }
 }
@@ -0,0 +1,137 @@
+//! Runs the `nurse` binary on the fixtures in `tests/fixtures/cli`.
+
+#![cfg(feature = "cli")]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_nurse"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(format!("tests/fixtures/cli/{name}")).unwrap()
+}
+
+/// Gets the lines of `output` that start a diagnostic or point to its location.
+fn headers(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| line.starts_with(char::is_alphabetic) || line.contains("——> "))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn rustc() {
+    let output = run(&[], &fixture("rustc.json"));
+    assert_eq!(output.status.code(), Some(1));
+
+    let mut headers = headers(&output);
+    // The error reading the file depends on the platform
+    assert!(headers[5].starts_with("warning: unable to read `tests/fixtures/cli/missing.rs`: "));
+    headers.remove(5);
+
+    assert_eq!(
+        headers,
+        [
+            "error[overflowing_literals]: literal out of range for `u8`",
+            " ——> [tests/fixtures/cli/main.rs:2:17]",
+            "warning[unused_variables]: unused variable: `x`",
+            " ——> [tests/fixtures/cli/main.rs:2:9]",
+            "warning: span out of range",
+            "warning: in a missing file",
+            "error: aborting due to 1 previous error; 4 warnings emitted",
+        ]
+    );
+}
+
+#[test]
+fn gcc() {
+    let output = run(&["--format", "gcc"], &fixture("gcc.json"));
+    assert_eq!(output.status.code(), Some(1));
+
+    let headers = headers(&output);
+    assert_eq!(headers[1], " ——> [tests/fixtures/cli/main.c:2:13]");
+    assert_eq!(headers[3], " ——> [tests/fixtures/cli/main.c:3:12]");
+    assert_eq!(headers[4], "error: line out of range");
+    assert_eq!(headers[5], "error: column out of range");
+}
+
+#[test]
+fn sarif() {
+    let output = run(&[], &fixture("sarif.json"));
+    assert_eq!(output.status.code(), Some(1));
+
+    let headers = headers(&output);
+    assert_eq!(headers[1], " ——> [tests/fixtures/cli/main.c:2:17]");
+    assert_eq!(headers[3], " ——> [tests/fixtures/cli/main.c:3:12]");
+    assert_eq!(headers[4], "info[C003]: line out of range");
+}
+
+#[test]
+fn nurse() {
+    let output = run(&["-f", "nurse"], &fixture("nurse.json"));
+    assert_eq!(output.status.code(), Some(1));
+    assert!(headers(&output).contains(&" ——> [tests/fixtures/cli/main.rs:2:17]".to_owned()));
+}
+
+#[test]
+fn warnings_only() {
+    let input = r#"{"level":"warn","message":"unused label"}"#;
+    let output = run(&[], input);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        headers(&output),
+        ["warning: unused label", "warning: 1 warning emitted"]
+    );
+}
+
+#[test]
+fn invalid_input() {
+    let cases: [(&[&str], &str, &str); 5] = [
+        (&[], "{\"level\": ", "error: invalid JSON input"),
+        (
+            &[],
+            r#"{"unknown": 1}"#,
+            "error: unrecognized diagnostic format",
+        ),
+        (
+            &["--format", "gcc"],
+            r#"{"kind": "error"}"#,
+            "error: invalid gcc diagnostic",
+        ),
+        (
+            &["--format", "json"],
+            "",
+            "error: expected a format after `--format`",
+        ),
+        (&["--verbose"], "", "error: unexpected argument `--verbose`"),
+    ];
+
+    for (args, input, error) in cases {
+        let output = run(args, input);
+        assert_eq!(output.status.code(), Some(2), "{args:?} {input}");
+        assert!(
+            String::from_utf8_lossy(&output.stderr).starts_with(error),
+            "{args:?} {input}"
+        );
+    }
+}
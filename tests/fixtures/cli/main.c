@@ -0,0 +1,4 @@
+int main(void) {
+    int y = 1 + "é";
+    return x;
+}